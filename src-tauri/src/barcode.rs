@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+
+use image::DynamicImage;
+use rxing::{BarcodeFormat, DecodeHints};
+use serde::Serialize;
+
+use crate::ocr_document::{rect_polygon, Point};
+
+/// A QR code or barcode found in a capture. `polygon` is in the pixel
+/// coordinates of the decoded image, like OCR line boxes.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedCode {
+    /// rxing's format name, e.g. "qrcode", "ean 13", "code 128".
+    pub format: String,
+    pub text: String,
+    pub polygon: Vec<Point>,
+}
+
+/// Captures smaller than this are upscaled first; the detectors need a few
+/// pixels per module and slide thumbnails rarely have them.
+const MIN_DECODE_SIZE: u32 = 400;
+
+/// Finds and decodes every QR code and common 1D barcode in the image. Light
+/// codes on dark backgrounds are retried inverted.
+pub fn decode(image: &DynamicImage) -> Vec<DecodedCode> {
+    let start = std::time::Instant::now();
+    let short_side = image.width().min(image.height()).max(1);
+    let factor = if short_side < MIN_DECODE_SIZE { MIN_DECODE_SIZE.div_ceil(short_side).min(4) } else { 1 };
+    let gray = if factor > 1 {
+        image
+            .resize_exact(image.width() * factor, image.height() * factor, image::imageops::FilterType::Nearest)
+            .to_luma8()
+    } else {
+        image.to_luma8()
+    };
+
+    let mut codes = decode_luma(&gray, factor as f32);
+    if codes.is_empty() {
+        let mut inverted = gray;
+        image::imageops::invert(&mut inverted);
+        codes = decode_luma(&inverted, factor as f32);
+    }
+    println!("barcode: {} code(s) found in {:?}", codes.len(), start.elapsed());
+    codes
+}
+
+fn decode_luma(gray: &image::GrayImage, scale: f32) -> Vec<DecodedCode> {
+    let formats: HashSet<BarcodeFormat> = [
+        BarcodeFormat::QR_CODE,
+        BarcodeFormat::EAN_13,
+        BarcodeFormat::EAN_8,
+        BarcodeFormat::UPC_A,
+        BarcodeFormat::UPC_E,
+        BarcodeFormat::CODE_128,
+        BarcodeFormat::CODE_39,
+    ]
+    .into_iter()
+    .collect();
+    let mut hints = DecodeHints { PossibleFormats: Some(formats), TryHarder: Some(true), ..Default::default() };
+
+    let results = match rxing::helpers::detect_multiple_in_luma_with_hints(gray.as_raw().clone(), gray.width(), gray.height(), &mut hints) {
+        Ok(results) => results,
+        // NotFound is the normal "nothing here" outcome
+        Err(rxing::Exceptions::NotFoundException(_)) => return Vec::new(),
+        Err(e) => {
+            println!("barcode: Decoder error: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut seen = HashSet::new();
+    results
+        .into_iter()
+        // The multi reader can report one code twice from overlapping scans
+        .filter(|r| seen.insert((r.getBarcodeFormat().to_string(), r.getText().to_string())))
+        .map(|r| {
+            let points = r.getPoints();
+            let polygon = points
+                .iter()
+                .map(|p| (p.x / scale, p.y / scale))
+                .fold(None, |acc: Option<(f32, f32, f32, f32)>, (x, y)| {
+                    Some(acc.map_or((x, y, x, y), |(x0, y0, x1, y1)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y))))
+                })
+                .map(|(x0, y0, x1, y1)| rect_polygon(x0, y0, x1 - x0, y1 - y0))
+                .unwrap_or_default();
+            DecodedCode { format: r.getBarcodeFormat().to_string(), text: r.getText().to_string(), polygon }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr_document::polygon_bounds;
+    use rxing::{MultiFormatWriter, Writer};
+
+    /// Renders `text` as dark modules on white, quiet zone included.
+    fn render(text: &str, format: BarcodeFormat, width: i32, height: i32) -> DynamicImage {
+        MultiFormatWriter.encode(text, &format, width, height).expect("encodable").into()
+    }
+
+    fn decoded(codes: &[DecodedCode]) -> Vec<(&str, &str)> {
+        codes.iter().map(|c| (c.format.as_str(), c.text.as_str())).collect()
+    }
+
+    #[test]
+    fn small_qr_code_is_upscaled_and_mapped_back() {
+        let image = render("https://example.com/booboo", BarcodeFormat::QR_CODE, 120, 120);
+        let codes = decode(&image);
+        assert_eq!(decoded(&codes), [("qrcode", "https://example.com/booboo")]);
+
+        // Finder pattern centres, in the 120 px crop rather than the 480 px upscale
+        let (x0, y0, x1, y1) = polygon_bounds(&codes[0].polygon).unwrap();
+        assert!(x0 >= 0.0 && y0 >= 0.0 && x1 <= 120.0 && y1 <= 120.0, "{:?}", (x0, y0, x1, y1));
+        assert!(x1 - x0 > 40.0 && y1 - y0 > 40.0, "{:?}", (x0, y0, x1, y1));
+    }
+
+    #[test]
+    fn light_on_dark_qr_code_is_decoded_inverted() {
+        let mut image = render("light on dark", BarcodeFormat::QR_CODE, 400, 400);
+        image.invert();
+        // Only the inverted retry can find it
+        assert!(decode_luma(&image.to_luma8(), 1.0).is_empty());
+        assert_eq!(decoded(&decode(&image)), [("qrcode", "light on dark")]);
+    }
+
+    #[test]
+    fn linear_barcodes_are_decoded() {
+        let code128 = render("BOOBOO-42", BarcodeFormat::CODE_128, 300, 80);
+        let codes = decode(&code128);
+        assert_eq!(decoded(&codes), [("code 128", "BOOBOO-42")]);
+        let (x0, _, x1, _) = polygon_bounds(&codes[0].polygon).unwrap();
+        assert!(x0 >= 0.0 && x1 <= 300.0 && x1 - x0 > 100.0, "{:?}", (x0, x1));
+
+        let ean13 = render("5901234123457", BarcodeFormat::EAN_13, 400, 120);
+        assert_eq!(decoded(&decode(&ean13)), [("ean 13", "5901234123457")]);
+    }
+}
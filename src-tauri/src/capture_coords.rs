@@ -0,0 +1,272 @@
+use crate::ocr_core::CaptureGeometry;
+
+/// A selection as drawn on the capture overlay, in the overlay's logical
+/// pixels relative to its top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogicalRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A rectangle in pixels of the cached screenshot (not desktop coordinates:
+/// (0, 0) is the capture's top-left corner, wherever that is on the desktop).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Where a selection landed in the screenshot, and how to get back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelectionMapping {
+    /// Area to crop, padding included, clipped to the capture.
+    pub crop: PixelRect,
+    /// Physical pixels per overlay logical pixel.
+    pub overlay_scale: f64,
+}
+
+impl SelectionMapping {
+    /// Maps a point in the cropped image back into overlay logical pixels,
+    /// as `(scale, offset_x, offset_y)` for `OcrDocument::transform`.
+    pub fn crop_to_overlay(&self) -> (f32, f32, f32) {
+        let inv = 1.0 / self.overlay_scale;
+        (inv as f32, (self.crop.x as f64 * inv) as f32, (self.crop.y as f64 * inv) as f32)
+    }
+
+    /// Top-left corner of the crop on the physical desktop.
+    pub fn desktop_origin(&self, geometry: &CaptureGeometry) -> (i32, i32) {
+        (geometry.x + self.crop.x as i32, geometry.y + self.crop.y as i32)
+    }
+}
+
+/// Converts an overlay selection into screenshot pixels.
+///
+/// The overlay covers exactly the captured area at a single DPI
+/// (`overlay_scale`), so its logical coordinates map linearly onto the
+/// screenshot even when the capture spans monitors with different scale
+/// factors or starts at a negative desktop position. `padding` is a margin in
+/// logical pixels of the monitor under the selection's center, added on every
+/// side before clipping to the capture. Returns `None` for selections that
+/// end up empty.
+pub fn map_selection(geometry: &CaptureGeometry, overlay_scale: f64, selection: LogicalRect, padding: f64) -> Option<SelectionMapping> {
+    if !(overlay_scale.is_finite() && overlay_scale > 0.0) {
+        return None;
+    }
+    // Normalize selections dragged up or to the left
+    let (lx0, lx1) = ordered(selection.x, selection.x + selection.width);
+    let (ly0, ly1) = ordered(selection.y, selection.y + selection.height);
+
+    // Round each edge rather than origin + size, so adjacent selections tile
+    // without gaps or overlap
+    let x0 = (lx0 * overlay_scale).round() as i64;
+    let y0 = (ly0 * overlay_scale).round() as i64;
+    let x1 = (lx1 * overlay_scale).round() as i64;
+    let y1 = (ly1 * overlay_scale).round() as i64;
+
+    let center_x = geometry.x as i64 + (x0 + x1) / 2;
+    let center_y = geometry.y as i64 + (y0 + y1) / 2;
+    let monitor_scale = geometry
+        .monitor_at(center_x.clamp(i32::MIN as i64, i32::MAX as i64) as i32, center_y.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+        .map_or(overlay_scale, |m| m.scale_factor);
+    let pad = (padding.max(0.0) * monitor_scale).round() as i64;
+
+    let clip_x = |v: i64| v.clamp(0, geometry.width as i64);
+    let clip_y = |v: i64| v.clamp(0, geometry.height as i64);
+    let (cx0, cx1) = (clip_x(x0 - pad), clip_x(x1 + pad));
+    let (cy0, cy1) = (clip_y(y0 - pad), clip_y(y1 + pad));
+    if cx1 <= cx0 || cy1 <= cy0 {
+        return None;
+    }
+
+    Some(SelectionMapping {
+        crop: PixelRect { x: cx0 as u32, y: cy0 as u32, width: (cx1 - cx0) as u32, height: (cy1 - cy0) as u32 },
+        overlay_scale,
+    })
+}
+
+fn ordered(a: f64, b: f64) -> (f64, f64) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr_core::MonitorInfo;
+
+    fn monitor(x: i32, y: i32, width: u32, height: u32, scale_factor: f64) -> MonitorInfo {
+        MonitorInfo { name: String::new(), x, y, width, height, scale_factor }
+    }
+
+    fn geometry(monitors: Vec<MonitorInfo>) -> CaptureGeometry {
+        let x0 = monitors.iter().map(|m| m.x).min().unwrap();
+        let y0 = monitors.iter().map(|m| m.y).min().unwrap();
+        let x1 = monitors.iter().map(|m| m.x + m.width as i32).max().unwrap();
+        let y1 = monitors.iter().map(|m| m.y + m.height as i32).max().unwrap();
+        CaptureGeometry { x: x0, y: y0, width: (x1 - x0) as u32, height: (y1 - y0) as u32, monitors }
+    }
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> LogicalRect {
+        LogicalRect { x, y, width, height }
+    }
+
+    fn px(x: u32, y: u32, width: u32, height: u32) -> PixelRect {
+        PixelRect { x, y, width, height }
+    }
+
+    #[test]
+    fn single_monitor_at_unit_scale() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        let m = map_selection(&g, 1.0, rect(100.0, 200.0, 300.0, 40.0), 0.0).unwrap();
+        assert_eq!(m.crop, px(100, 200, 300, 40));
+    }
+
+    #[test]
+    fn single_monitor_high_dpi() {
+        let g = geometry(vec![monitor(0, 0, 3840, 2160, 2.0)]);
+        let m = map_selection(&g, 2.0, rect(100.0, 200.0, 300.0, 40.0), 0.0).unwrap();
+        assert_eq!(m.crop, px(200, 400, 600, 80));
+    }
+
+    #[test]
+    fn fractional_scale_rounds_edges() {
+        let g = geometry(vec![monitor(0, 0, 2880, 1620, 1.5)]);
+        let m = map_selection(&g, 1.5, rect(10.5, 11.0, 20.0, 21.0), 0.0).unwrap();
+        // 15.75 -> 16, 45.75 -> 46, 16.5 -> 17, 48 -> 48
+        assert_eq!(m.crop, px(16, 17, 30, 31));
+    }
+
+    #[test]
+    fn adjacent_selections_tile() {
+        let g = geometry(vec![monitor(0, 0, 2880, 1620, 1.5)]);
+        let a = map_selection(&g, 1.5, rect(0.0, 0.0, 33.3, 10.0), 0.0).unwrap().crop;
+        let b = map_selection(&g, 1.5, rect(33.3, 0.0, 33.3, 10.0), 0.0).unwrap().crop;
+        assert_eq!(a.x + a.width, b.x);
+    }
+
+    #[test]
+    fn padding_expands_every_side() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        let m = map_selection(&g, 1.0, rect(100.0, 100.0, 50.0, 20.0), 15.0).unwrap();
+        assert_eq!(m.crop, px(85, 85, 80, 50));
+    }
+
+    #[test]
+    fn padding_is_clipped_at_capture_edges() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        let m = map_selection(&g, 1.0, rect(5.0, 0.0, 50.0, 20.0), 15.0).unwrap();
+        assert_eq!(m.crop, px(0, 0, 70, 35));
+        let m = map_selection(&g, 1.0, rect(1900.0, 1070.0, 20.0, 10.0), 15.0).unwrap();
+        assert_eq!(m.crop, px(1885, 1055, 35, 25));
+    }
+
+    #[test]
+    fn padding_uses_dpi_of_monitor_under_selection() {
+        // 4K at 200% on the left, 1080p at 100% on the right, overlay at 200%
+        let g = geometry(vec![monitor(0, 0, 3840, 2160, 2.0), monitor(3840, 0, 1920, 1080, 1.0)]);
+        let left = map_selection(&g, 2.0, rect(100.0, 100.0, 10.0, 10.0), 10.0).unwrap();
+        assert_eq!(left.crop, px(180, 180, 60, 60));
+        let right = map_selection(&g, 2.0, rect(2000.0, 100.0, 10.0, 10.0), 10.0).unwrap();
+        assert_eq!(right.crop, px(3990, 190, 40, 40));
+    }
+
+    #[test]
+    fn negative_origin_is_relative_to_capture() {
+        // Secondary monitor left of the primary
+        let g = geometry(vec![monitor(-1920, 0, 1920, 1080, 1.0), monitor(0, 0, 1920, 1080, 1.0)]);
+        assert_eq!((g.x, g.width), (-1920, 3840));
+        let m = map_selection(&g, 1.0, rect(2000.0, 10.0, 100.0, 50.0), 0.0).unwrap();
+        assert_eq!(m.crop, px(2000, 10, 100, 50));
+        assert_eq!(m.desktop_origin(&g), (80, 10));
+    }
+
+    #[test]
+    fn monitor_above_primary() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0), monitor(200, -1440, 2560, 1440, 1.25)]);
+        assert_eq!((g.x, g.y, g.width, g.height), (0, -1440, 2760, 2520));
+        let m = map_selection(&g, 1.0, rect(300.0, 100.0, 40.0, 40.0), 8.0).unwrap();
+        // Center is on the upper monitor (125%): 8 * 1.25 = 10 px of padding
+        assert_eq!(m.crop, px(290, 90, 60, 60));
+        assert_eq!(m.desktop_origin(&g), (290, -1350));
+    }
+
+    #[test]
+    fn mixed_dpi_with_negative_origin() {
+        let g = geometry(vec![monitor(-2560, -200, 2560, 1440, 1.0), monitor(0, 0, 3840, 2160, 1.5)]);
+        assert_eq!((g.x, g.y), (-2560, -200));
+        // Overlay placed on the 150% monitor
+        let m = map_selection(&g, 1.5, rect(100.0, 200.0, 100.0, 100.0), 0.0).unwrap();
+        assert_eq!(m.crop, px(150, 300, 150, 150));
+        assert_eq!(m.desktop_origin(&g), (-2410, 100));
+    }
+
+    #[test]
+    fn selection_in_gap_between_monitors_uses_nearest() {
+        // Monitors of different heights leave an uncaptured gap bottom-right
+        let g = geometry(vec![monitor(0, 0, 1920, 1200, 1.0), monitor(1920, 0, 1920, 1080, 2.0)]);
+        let m = map_selection(&g, 1.0, rect(3000.0, 1150.0, 10.0, 10.0), 5.0).unwrap();
+        assert_eq!(m.crop, px(2990, 1140, 30, 30));
+    }
+
+    #[test]
+    fn reversed_selection_is_normalized() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        let m = map_selection(&g, 1.0, rect(400.0, 300.0, -100.0, -50.0), 0.0).unwrap();
+        assert_eq!(m.crop, px(300, 250, 100, 50));
+    }
+
+    #[test]
+    fn selection_outside_capture_is_rejected() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        assert!(map_selection(&g, 1.0, rect(2000.0, 10.0, 50.0, 50.0), 0.0).is_none());
+        assert!(map_selection(&g, 1.0, rect(-100.0, -100.0, 50.0, 50.0), 0.0).is_none());
+    }
+
+    #[test]
+    fn partially_outside_selection_is_clipped() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        let m = map_selection(&g, 1.0, rect(-50.0, 1000.0, 100.0, 200.0), 0.0).unwrap();
+        assert_eq!(m.crop, px(0, 1000, 50, 80));
+    }
+
+    #[test]
+    fn empty_selection_is_rejected_without_padding() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        assert!(map_selection(&g, 1.0, rect(10.0, 10.0, 0.0, 0.0), 0.0).is_none());
+        assert_eq!(map_selection(&g, 1.0, rect(10.0, 10.0, 0.0, 0.0), 2.0).unwrap().crop, px(8, 8, 4, 4));
+    }
+
+    #[test]
+    fn invalid_scale_is_rejected() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        assert!(map_selection(&g, 0.0, rect(10.0, 10.0, 5.0, 5.0), 0.0).is_none());
+        assert!(map_selection(&g, f64::NAN, rect(10.0, 10.0, 5.0, 5.0), 0.0).is_none());
+    }
+
+    #[test]
+    fn negative_padding_is_ignored() {
+        let g = geometry(vec![monitor(0, 0, 1920, 1080, 1.0)]);
+        let m = map_selection(&g, 1.0, rect(100.0, 100.0, 50.0, 20.0), -5.0).unwrap();
+        assert_eq!(m.crop, px(100, 100, 50, 20));
+    }
+
+    #[test]
+    fn crop_maps_back_to_overlay() {
+        let g = geometry(vec![monitor(-1920, 0, 1920, 1080, 1.0), monitor(0, 0, 3840, 2160, 2.0)]);
+        let m = map_selection(&g, 2.0, rect(1000.0, 100.0, 50.0, 20.0), 10.0).unwrap();
+        let (scale, ox, oy) = m.crop_to_overlay();
+        // The crop's top-left corner lands at the padded selection corner
+        assert_eq!(scale, 0.5);
+        assert_eq!((ox, oy), (990.0, 90.0));
+        // A point 40 physical pixels into the crop is 20 logical pixels in
+        let (x, y) = (40.0 * scale + ox, 40.0 * scale + oy);
+        assert_eq!((x, y), (1010.0, 110.0));
+    }
+}
@@ -1,34 +1,127 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Mutex;
 use tauri::{State, Manager};
+use once_cell::sync::OnceCell;
+
+use crate::image_preprocess::{PreprocessStep, ScaleFilter};
+
+// Set once at startup; relative engine/model paths are resolved against it.
+static RESOURCE_DIR: OnceCell<PathBuf> = OnceCell::new();
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct AppConfig {
     pub ocr_engine: String,       // "paddle" | "windows"
+    pub ocr_fallback: Vec<String>, // tried in order when ocr_engine fails
     pub translate_engine: String, // "google" | "youdao"
     pub youdao_app_key: String,
     pub youdao_app_secret: String,
     pub coze_api_key: String,     // Future proofing
     pub shortcut: String,
     pub shortcut_pin: String,
+    // PaddleOCR-json. Relative paths are resolved against the bundled resource dir.
+    pub paddle_engine_path: String,
+    pub paddle_models_dir: String,  // "" = engine default
+    pub paddle_config_path: String, // "" = engine default, e.g. "models/config_japan.txt"
+    pub paddle_extra_args: Vec<String>, // e.g. ["--use_angle_cls=true"]
+    pub paddle_timeout_ms: u64,     // per request; the process is restarted when exceeded
+    pub paddle_image_transport: String, // "base64" | "file"
+    pub paddle_workers: usize,      // engine processes; capped at the CPU count
+    pub paddle_idle_timeout_secs: u64, // unload the model after this long unused; 0 = never
+    // Tesseract CLI. A bare name is looked up on PATH.
+    pub tesseract_path: String,
+    pub tesseract_languages: Vec<String>, // pack names, e.g. ["chi_sim", "eng"]
+    pub tesseract_timeout_ms: u64, // per recognition; the process is killed when exceeded
+    // Windows OCR. One recognition pass per tag; empty = user profile languages.
+    pub windows_ocr_languages: Vec<String>, // BCP-47, e.g. ["zh-Hans", "en-US", "ja"]
+    pub ocr_merge_paragraphs: bool, // rejoin wrapped lines into paragraphs before translation
+    pub ocr_deskew: bool,     // straighten skewed captures and detect vertical/sideways text
+    pub ocr_polarity: String, // "auto" (invert dark regions) | "dual" (OCR both, keep the better) | "off"
+    // Text normalization applied to every engine's output
+    pub ocr_nfkc: bool,       // Unicode NFKC (ligatures, halfwidth katakana, circled digits)
+    pub ocr_punctuation_width: String, // "auto" (follow surrounding script) | "full" | "half" | "keep"
+    pub ocr_cjk_latin_spacing: String, // "keep" | "remove" | "insert"
+    pub ocr_decode_barcodes: bool, // also decode QR codes/barcodes on every text capture
+    pub capture_scope: String, // "cursor" (monitor under the cursor) | "all" (every monitor, stitched)
+    pub ocr_capture_padding: f64, // margin around OCR selections, in logical px of the monitor under them
+    // Image pipeline run before each engine, keyed by engine name
+    pub ocr_preprocess: HashMap<String, Vec<PreprocessStep>>,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             ocr_engine: "paddle".to_string(),
+            ocr_fallback: vec![default_fallback_engine().to_string()],
             translate_engine: "google".to_string(),
             youdao_app_key: "".to_string(),
             youdao_app_secret: "".to_string(),
             coze_api_key: "".to_string(),
             shortcut: "Alt+Shift+A".to_string(),
             shortcut_pin: "Alt+Shift+S".to_string(),
+            paddle_engine_path: default_paddle_engine_path().to_string(),
+            paddle_models_dir: "".to_string(),
+            paddle_config_path: "".to_string(),
+            paddle_extra_args: Vec::new(),
+            paddle_timeout_ms: 15000,
+            paddle_image_transport: "base64".to_string(),
+            paddle_workers: 1,
+            paddle_idle_timeout_secs: 600,
+            tesseract_path: "tesseract".to_string(),
+            tesseract_languages: vec!["chi_sim".to_string(), "eng".to_string()],
+            tesseract_timeout_ms: 15000,
+            windows_ocr_languages: vec!["zh-Hans".to_string(), "en-US".to_string()],
+            ocr_merge_paragraphs: true,
+            ocr_deskew: true,
+            ocr_polarity: "auto".to_string(),
+            ocr_nfkc: false,
+            ocr_punctuation_width: "auto".to_string(),
+            ocr_cjk_latin_spacing: "keep".to_string(),
+            ocr_decode_barcodes: false,
+            capture_scope: "cursor".to_string(),
+            ocr_capture_padding: 15.0,
+            ocr_preprocess: default_ocr_preprocess(),
         }
     }
 }
 
+impl AppConfig {
+    pub fn preprocess_steps(&self, engine: &str) -> &[PreprocessStep] {
+        self.ocr_preprocess.get(engine).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+fn default_ocr_preprocess() -> HashMap<String, Vec<PreprocessStep>> {
+    HashMap::from([
+        ("paddle".to_string(), Vec::new()),
+        ("tesseract".to_string(), Vec::new()),
+        // Lanczos3 at 2.5x is the best trade-off found between sharpness on thin
+        // fonts and ringing around punctuation; Triangle at 3x was too blurry.
+        (
+            "windows".to_string(),
+            vec![
+                PreprocessStep::Scale { factor: 2.5, filter: ScaleFilter::Lanczos3 },
+                PreprocessStep::Grayscale,
+            ],
+        ),
+    ])
+}
+
+fn default_fallback_engine() -> &'static str {
+    if cfg!(target_os = "windows") { "windows" } else { "tesseract" }
+}
+
+fn default_paddle_engine_path() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "ocr-engine/PaddleOCR-json_v1.4.1/PaddleOCR-json.exe"
+    } else {
+        "ocr-engine/PaddleOCR-json_linux/bin/PaddleOCR-json"
+    }
+}
+
 pub struct ConfigState {
     pub config: Mutex<AppConfig>,
     pub file_path: PathBuf,
@@ -41,6 +134,10 @@ impl ConfigState {
             let _ = fs::create_dir_all(&config_dir);
         }
         let file_path = config_dir.join("booboo_config.json");
+
+        if let Ok(resource_dir) = app_handle.path().resource_dir() {
+            let _ = RESOURCE_DIR.set(resource_dir);
+        }
         
         let config = if file_path.exists() {
             let content = fs::read_to_string(&file_path).unwrap_or_default();
@@ -62,6 +159,31 @@ impl ConfigState {
     }
 }
 
+/// Resolves a user-supplied path. Absolute paths are used as-is; relative ones
+/// are looked up in the resource dir first, then the working dir and its parent
+/// (the latter covers `tauri dev`, which runs from `src-tauri`).
+pub fn resolve_resource_path(path: &str) -> PathBuf {
+    let path = PathBuf::from(path.trim());
+    if path.is_absolute() {
+        return path;
+    }
+
+    let cwd = std::env::current_dir().ok();
+    let candidates = RESOURCE_DIR.get().cloned().into_iter()
+        .chain(cwd.clone())
+        .chain(cwd.and_then(|d| d.parent().map(|p| p.to_path_buf())));
+
+    let mut first = None;
+    for base in candidates {
+        let candidate = base.join(&path);
+        if candidate.exists() {
+            return candidate;
+        }
+        first.get_or_insert(candidate);
+    }
+    first.unwrap_or(path)
+}
+
 #[tauri::command]
 pub fn get_config(state: State<ConfigState>) -> Result<AppConfig, String> {
     let config = state.config.lock().map_err(|e| e.to_string())?;
@@ -0,0 +1,524 @@
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage};
+
+use crate::ocr_document::{rect_polygon, OcrDocument, OcrLine, Point};
+
+/// Largest skew we try to correct. Anything steeper is more likely a layout
+/// feature (diagonal banner, chart label) than a crooked photo.
+const MAX_SKEW_DEGREES: f32 = 15.0;
+/// Smaller skews don't hurt recognition and aren't worth the resampling blur.
+const MIN_SKEW_DEGREES: f32 = 0.5;
+/// Captures are measured at this size at most; plenty for line geometry.
+const ANALYSIS_SIZE: u32 = 800;
+
+/// How a capture is transformed before recognition. `apply` produces the image
+/// the engines see and `restore` maps their result back onto the capture.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Orientation {
+    Upright,
+    /// Rotated clockwise by this many degrees about its centre, on a canvas
+    /// grown to fit. Covers both deskew and quarter turns.
+    Rotated(f32),
+    /// Top-to-bottom, right-to-left CJK columns, rebuilt as one horizontal row
+    /// per column so glyphs keep their orientation.
+    VerticalColumns(Vec<Column>),
+}
+
+/// A text column and the glyph cells inside it, in capture pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub x0: u32,
+    pub x1: u32,
+    pub y0: u32,
+    pub y1: u32,
+    /// `(top, bottom)` of each glyph cell, top to bottom.
+    pub cells: Vec<(u32, u32)>,
+}
+
+/// Candidate orientations for a capture, best guess first. Usually just one;
+/// vertical line structure is ambiguous between CJK columns and sideways text,
+/// so that case yields all three readings for the caller to score.
+pub fn detect(image: &DynamicImage) -> Vec<Orientation> {
+    let gray = image.to_luma8();
+    let (w, h) = gray.dimensions();
+    if w < 16 || h < 16 {
+        return vec![Orientation::Upright];
+    }
+    let threshold = otsu_threshold(&gray);
+    let dark_ink = ink_is_dark(&gray, threshold);
+
+    // Line geometry on a reduced copy
+    let factor = (ANALYSIS_SIZE as f32 / w.max(h) as f32).min(1.0);
+    let small = if factor < 1.0 {
+        image::imageops::resize(&gray, ((w as f32 * factor) as u32).max(1), ((h as f32 * factor) as u32).max(1), image::imageops::FilterType::Triangle)
+    } else {
+        gray.clone()
+    };
+    let points: Vec<(f32, f32)> = small
+        .enumerate_pixels()
+        .filter(|(_, _, p)| is_ink(p.0[0], threshold, dark_ink))
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+    if points.len() < 50 {
+        return vec![Orientation::Upright];
+    }
+
+    // Text lines leave the strongest gaps across the reading direction
+    let (mut rows, mut cols) = (vec![0u32; small.height() as usize], vec![0u32; small.width() as usize]);
+    for (x, y) in &points {
+        rows[*y as usize] += 1;
+        cols[*x as usize] += 1;
+    }
+    let lines_run_vertically = profile_peakiness(&cols, points.len()) > profile_peakiness(&rows, points.len());
+
+    let columns = if lines_run_vertically { find_columns(&gray, threshold, dark_ink) } else { Vec::new() };
+    if looks_vertical(&columns) {
+        println!("image_orientation: {} vertical column(s) detected", columns.len());
+        return vec![Orientation::VerticalColumns(columns), Orientation::Rotated(90.0), Orientation::Rotated(-90.0)];
+    }
+
+    let skew = estimate_skew(&points);
+    if skew.abs() >= MIN_SKEW_DEGREES {
+        println!("image_orientation: skew {:.1}°", skew);
+        // Rotating by the opposite angle levels the lines
+        return vec![Orientation::Rotated(-skew)];
+    }
+    vec![Orientation::Upright]
+}
+
+impl Orientation {
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        match self {
+            Orientation::Upright => image.clone(),
+            Orientation::Rotated(degrees) if *degrees == 90.0 => image.rotate90(),
+            Orientation::Rotated(degrees) if *degrees == -90.0 => image.rotate270(),
+            Orientation::Rotated(degrees) => rotate(image, *degrees),
+            Orientation::VerticalColumns(columns) => rebuild_columns(image, columns),
+        }
+    }
+
+    /// Maps boxes from the transformed image back onto a `width` x `height`
+    /// capture and records what was done.
+    pub fn restore(&self, doc: &mut OcrDocument, width: u32, height: u32) {
+        match self {
+            Orientation::Upright => {}
+            Orientation::Rotated(degrees) => {
+                let (dst_w, dst_h) = rotated_size(width, height, *degrees);
+                let (sin, cos) = (-degrees.to_radians()).sin_cos();
+                let (src_cx, src_cy) = (width as f32 / 2.0, height as f32 / 2.0);
+                let (dst_cx, dst_cy) = (dst_w as f32 / 2.0, dst_h as f32 / 2.0);
+                doc.map_points(|p| {
+                    let (dx, dy) = (p.x - dst_cx, p.y - dst_cy);
+                    Point { x: src_cx + dx * cos - dy * sin, y: src_cy + dx * sin + dy * cos }
+                });
+                doc.rotation = *degrees;
+            }
+            Orientation::VerticalColumns(columns) => {
+                doc.lines = restore_columns(std::mem::take(&mut doc.lines), columns);
+                doc.vertical = true;
+            }
+        }
+        doc.width = width;
+        doc.height = height;
+    }
+}
+
+fn otsu_threshold(gray: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for p in gray.pixels() {
+        histogram[p.0[0] as usize] += 1;
+    }
+    let total: u64 = histogram.iter().sum();
+    let sum_all: f64 = histogram.iter().enumerate().map(|(i, c)| i as f64 * *c as f64).sum();
+
+    let (mut best, mut best_var) = (128u8, 0.0f64);
+    let (mut weight_bg, mut sum_bg) = (0u64, 0.0f64);
+    for (t, count) in histogram.iter().enumerate() {
+        weight_bg += count;
+        if weight_bg == 0 || weight_bg == total {
+            continue;
+        }
+        sum_bg += t as f64 * *count as f64;
+        let weight_fg = total - weight_bg;
+        let mean_bg = sum_bg / weight_bg as f64;
+        let mean_fg = (sum_all - sum_bg) / weight_fg as f64;
+        let var = weight_bg as f64 * weight_fg as f64 * (mean_bg - mean_fg).powi(2);
+        if var > best_var {
+            best_var = var;
+            best = t as u8;
+        }
+    }
+    best
+}
+
+/// Ink is whichever side of the threshold has fewer pixels, so this works
+/// for dark-mode captures that were not inverted.
+fn ink_is_dark(gray: &GrayImage, threshold: u8) -> bool {
+    let dark = gray.pixels().filter(|p| p.0[0] <= threshold).count();
+    dark * 2 <= gray.pixels().len()
+}
+
+fn is_ink(value: u8, threshold: u8, dark_ink: bool) -> bool {
+    if dark_ink { value <= threshold } else { value > threshold }
+}
+
+/// Sum of squared bin counts, normalized so a flat profile scores 1.
+fn profile_peakiness(bins: &[u32], points: usize) -> f32 {
+    let squares: f64 = bins.iter().map(|c| (*c as f64).powi(2)).sum();
+    (squares * bins.len() as f64 / (points as f64).powi(2)) as f32
+}
+
+/// Projection-profile skew estimate: text lines produce the sharpest row
+/// profile when projected along their own angle.
+fn estimate_skew(points: &[(f32, f32)]) -> f32 {
+    let max_extent = points.iter().fold(0.0f32, |m, (x, y)| m.max(*x).max(*y));
+    let offset = max_extent * 2.0;
+    let bin_count = (offset * 2.0) as usize + 1;
+    let score = |degrees: f32| {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut bins = vec![0u32; bin_count];
+        for (x, y) in points {
+            let row = (y * cos - x * sin + offset) as usize;
+            if let Some(bin) = bins.get_mut(row) {
+                *bin += 1;
+            }
+        }
+        profile_peakiness(&bins, points.len())
+    };
+
+    let search = |from: f32, to: f32, step: f32| {
+        let steps = ((to - from) / step).round() as i32;
+        (0..=steps)
+            .map(|i| from + i as f32 * step)
+            .map(|a| (a, score(a)))
+            .fold((0.0f32, f32::MIN), |best, cur| if cur.1 > best.1 { cur } else { best })
+    };
+
+    let (coarse, _) = search(-MAX_SKEW_DEGREES, MAX_SKEW_DEGREES, 1.0);
+    let (fine, fine_score) = search(coarse - 1.0, coarse + 1.0, 0.1);
+
+    // Ignore marginal wins; a flat profile is noisy around zero
+    if fine_score < score(0.0) * 1.05 {
+        return 0.0;
+    }
+    fine
+}
+
+/// Runs of columns that contain ink, split where a blank gap appears.
+fn find_columns(gray: &GrayImage, threshold: u8, dark_ink: bool) -> Vec<Column> {
+    let (w, h) = gray.dimensions();
+    let mut column_ink = vec![0u32; w as usize];
+    for (x, _, p) in gray.enumerate_pixels() {
+        if is_ink(p.0[0], threshold, dark_ink) {
+            column_ink[x as usize] += 1;
+        }
+    }
+
+    let runs = ink_runs(&column_ink, 1);
+    let widest = runs.iter().map(|(a, b)| b - a).max().unwrap_or(0);
+    runs.into_iter()
+        // Drop specks and stray strokes next to real columns
+        .filter(|(a, b)| (b - a) * 2 >= widest)
+        .filter_map(|(x0, x1)| {
+            let mut row_ink = vec![0u32; h as usize];
+            for y in 0..h {
+                for x in x0..x1 {
+                    if is_ink(gray.get_pixel(x, y).0[0], threshold, dark_ink) {
+                        row_ink[y as usize] += 1;
+                    }
+                }
+            }
+            let cells = glyph_cells(ink_runs(&row_ink, 1), x1 - x0);
+            let (y0, y1) = (cells.first()?.0, cells.last()?.1);
+            Some(Column { x0, x1, y0, y1, cells })
+        })
+        .collect()
+}
+
+/// `[start, end)` ranges where `profile` is non-zero, bridging gaps of up to
+/// `max_gap` empty entries.
+fn ink_runs(profile: &[u32], max_gap: usize) -> Vec<(u32, u32)> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for (i, count) in profile.iter().enumerate() {
+        if *count == 0 {
+            continue;
+        }
+        let i = i as u32;
+        match runs.last_mut() {
+            Some(last) if (i - last.1) as usize <= max_gap => last.1 = i + 1,
+            _ => runs.push((i, i + 1)),
+        }
+    }
+    runs
+}
+
+/// Merges vertical ink runs into roughly square cells. Glyphs like 二 or 三
+/// have internal gaps; a cell grows until it would be taller than the column
+/// is wide.
+fn glyph_cells(runs: Vec<(u32, u32)>, column_width: u32) -> Vec<(u32, u32)> {
+    let limit = (column_width as f32 * 1.15) as u32;
+    let mut cells: Vec<(u32, u32)> = Vec::new();
+    for (top, bottom) in runs {
+        match cells.last_mut() {
+            Some(cell) if bottom - cell.0 <= limit => cell.1 = bottom,
+            _ => cells.push((top, bottom)),
+        }
+    }
+    cells
+}
+
+/// Columns of text are long and thin; a row of Latin letters split at its
+/// letter gaps is not.
+fn looks_vertical(columns: &[Column]) -> bool {
+    if columns.is_empty() {
+        return false;
+    }
+    let width: u32 = columns.iter().map(|c| c.x1 - c.x0).sum();
+    let height: u32 = columns.iter().map(|c| c.y1 - c.y0).sum();
+    let cells: usize = columns.iter().map(|c| c.cells.len()).sum();
+    height as f32 >= width as f32 * 2.5 && cells >= columns.len() * 3
+}
+
+fn background_color(image: &RgbaImage) -> Rgba<u8> {
+    let (w, h) = image.dimensions();
+    let mut border: Vec<Rgba<u8>> = Vec::new();
+    for x in 0..w {
+        border.push(*image.get_pixel(x, 0));
+        border.push(*image.get_pixel(x, h - 1));
+    }
+    for y in 0..h {
+        border.push(*image.get_pixel(0, y));
+        border.push(*image.get_pixel(w - 1, y));
+    }
+    border.sort_by_key(|p| p.0[0] as u32 + p.0[1] as u32 + p.0[2] as u32);
+    border.get(border.len() / 2).copied().unwrap_or(Rgba([255, 255, 255, 255]))
+}
+
+fn rotated_size(width: u32, height: u32, degrees: f32) -> (u32, u32) {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (sin, cos) = (sin.abs(), cos.abs());
+    // cos(90°) comes out as ~4e-8, not 0; don't let that round a quarter
+    // turn up to an extra pixel
+    let w = (width as f32 * cos + height as f32 * sin - 1e-3).ceil() as u32;
+    let h = (width as f32 * sin + height as f32 * cos - 1e-3).ceil() as u32;
+    (w.max(1), h.max(1))
+}
+
+/// Bilinear rotation about the centre. The corners that open up are filled
+/// with the capture's background so they don't read as ink.
+fn rotate(image: &DynamicImage, degrees: f32) -> DynamicImage {
+    let src = image.to_rgba8();
+    let (w, h) = src.dimensions();
+    let (dst_w, dst_h) = rotated_size(w, h, degrees);
+    let fill = background_color(&src);
+    let (sin, cos) = (-degrees.to_radians()).sin_cos();
+    let (src_cx, src_cy) = (w as f32 / 2.0, h as f32 / 2.0);
+    let (dst_cx, dst_cy) = (dst_w as f32 / 2.0, dst_h as f32 / 2.0);
+
+    let out = RgbaImage::from_fn(dst_w, dst_h, |x, y| {
+        let (dx, dy) = (x as f32 + 0.5 - dst_cx, y as f32 + 0.5 - dst_cy);
+        let sx = src_cx + dx * cos - dy * sin - 0.5;
+        let sy = src_cy + dx * sin + dy * cos - 0.5;
+        if sx < -0.5 || sy < -0.5 || sx > w as f32 - 0.5 || sy > h as f32 - 0.5 {
+            return fill;
+        }
+
+        let (x0, y0) = (sx.floor().max(0.0) as u32, sy.floor().max(0.0) as u32);
+        let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+        let (fx, fy) = ((sx - x0 as f32).clamp(0.0, 1.0), (sy - y0 as f32).clamp(0.0, 1.0));
+        let (a, b, c, d) = (src.get_pixel(x0, y0), src.get_pixel(x1, y0), src.get_pixel(x0, y1), src.get_pixel(x1, y1));
+        let mut px = [0u8; 4];
+        for (i, out) in px.iter_mut().enumerate() {
+            let top = a.0[i] as f32 * (1.0 - fx) + b.0[i] as f32 * fx;
+            let bottom = c.0[i] as f32 * (1.0 - fx) + d.0[i] as f32 * fx;
+            *out = (top * (1.0 - fy) + bottom * fy).round() as u8;
+        }
+        Rgba(px)
+    });
+    DynamicImage::ImageRgba8(out)
+}
+
+/// Row geometry of the rebuilt image: every column becomes one row of this
+/// height, rightmost column first.
+fn row_height(columns: &[Column]) -> u32 {
+    let widest = columns.iter().map(|c| c.x1 - c.x0).max().unwrap_or(1);
+    widest + widest / 2
+}
+
+fn rebuild_columns(image: &DynamicImage, columns: &[Column]) -> DynamicImage {
+    let src = image.to_rgba8();
+    let row_h = row_height(columns);
+    let gap = (row_h / 6).max(2);
+    let row_w = columns
+        .iter()
+        .map(|c| c.cells.len() as u32 * (c.x1 - c.x0 + gap) + gap)
+        .max()
+        .unwrap_or(1);
+
+    let mut out = RgbaImage::from_pixel(row_w, row_h * columns.len() as u32, background_color(&src));
+    for (row, column) in columns.iter().rev().enumerate() {
+        let col_w = column.x1 - column.x0;
+        let mut x = gap;
+        for (top, bottom) in &column.cells {
+            let cell_h = bottom - top;
+            let y = row as u32 * row_h + (row_h.saturating_sub(cell_h)) / 2;
+            let glyph = image::imageops::crop_imm(&src, column.x0, *top, col_w, cell_h).to_image();
+            image::imageops::replace(&mut out, &glyph, x as i64, y as i64);
+            x += col_w + gap;
+        }
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+/// Folds recognized lines back into one line per column, boxed by the column.
+fn restore_columns(lines: Vec<OcrLine>, columns: &[Column]) -> Vec<OcrLine> {
+    let row_h = row_height(columns) as f32;
+    let mut rows: Vec<Vec<OcrLine>> = vec![Vec::new(); columns.len()];
+    for line in lines {
+        let Some(center) = crate::ocr_document::polygon_bounds(&line.polygon).map(|(_, y0, _, y1)| (y0 + y1) / 2.0) else { continue };
+        let row = ((center / row_h) as usize).min(columns.len() - 1);
+        rows[row].push(line);
+    }
+
+    rows.into_iter()
+        .zip(columns.iter().rev())
+        .filter(|(parts, _)| !parts.is_empty())
+        .map(|(mut parts, column)| {
+            parts.sort_by(|a, b| {
+                let ax = a.polygon.first().map(|p| p.x).unwrap_or(0.0);
+                let bx = b.polygon.first().map(|p| p.x).unwrap_or(0.0);
+                ax.total_cmp(&bx)
+            });
+            let scores: Vec<f32> = parts.iter().filter_map(|l| l.confidence).collect();
+            OcrLine {
+                text: parts.iter().map(|l| l.text.as_str()).collect::<String>(),
+                words: Vec::new(),
+                polygon: rect_polygon(column.x0 as f32, column.y0 as f32, (column.x1 - column.x0) as f32, (column.y1 - column.y0) as f32),
+                confidence: (!scores.is_empty()).then(|| scores.iter().sum::<f32>() / scores.len() as f32),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr_document::polygon_bounds;
+    use image::Luma;
+
+    fn blank(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_pixel(width, height, Luma([255]))
+    }
+
+    fn fill(image: &mut GrayImage, x0: u32, y0: u32, x1: u32, y1: u32) {
+        for y in y0..y1 {
+            for x in x0..x1 {
+                image.put_pixel(x, y, Luma([0]));
+            }
+        }
+    }
+
+    /// Bounding box of the dark pixels in rows `y0..y1`, as a line box would
+    /// report it.
+    fn ink_bounds(image: &DynamicImage, y0: u32, y1: u32) -> (f32, f32, f32, f32) {
+        let gray = image.to_luma8();
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+        for (x, y, _) in gray.enumerate_pixels().filter(|(_, y, p)| (y0..y1).contains(y) && p.0[0] < 128) {
+            (min_x, min_y, max_x, max_y) = (min_x.min(x), min_y.min(y), max_x.max(x + 1), max_y.max(y + 1));
+        }
+        assert!(min_x < max_x, "no ink in rows {}..{}", y0, y1);
+        (min_x as f32, min_y as f32, max_x as f32, max_y as f32)
+    }
+
+    fn line(text: &str, (x0, y0, x1, y1): (f32, f32, f32, f32)) -> OcrLine {
+        OcrLine { text: text.to_string(), words: Vec::new(), polygon: rect_polygon(x0, y0, x1 - x0, y1 - y0), confidence: None }
+    }
+
+    fn assert_close(actual: (f32, f32, f32, f32), expected: (f32, f32, f32, f32), tolerance: f32) {
+        let pairs = [(actual.0, expected.0), (actual.1, expected.1), (actual.2, expected.2), (actual.3, expected.3)];
+        assert!(pairs.iter().all(|(a, e)| (a - e).abs() <= tolerance), "{:?} is not within {} of {:?}", actual, tolerance, expected);
+    }
+
+    /// Dashed horizontal strokes, like a few lines of text.
+    fn text_lines(width: u32, height: u32) -> GrayImage {
+        let mut image = blank(width, height);
+        for y in (30..height - 30).step_by(24) {
+            for x in (30..width - 30).step_by(12) {
+                fill(&mut image, x, y, x + 8, y + 6);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn estimates_skew_of_rotated_lines() {
+        let level = DynamicImage::ImageLuma8(text_lines(400, 200));
+        for degrees in [4.0f32, -6.0] {
+            let crooked = rotate(&level, degrees);
+            match detect(&crooked).as_slice() {
+                [Orientation::Rotated(correction)] => {
+                    assert!((correction + degrees).abs() <= 0.3, "rotated by {}, corrected by {}", degrees, correction)
+                }
+                other => panic!("rotated by {}, detected {:?}", degrees, other),
+            }
+        }
+        assert_eq!(detect(&level), vec![Orientation::Upright]);
+    }
+
+    #[test]
+    fn rotated_boxes_restore_onto_the_capture() {
+        let (width, height) = (240, 120);
+        let mut capture = blank(width, height);
+        fill(&mut capture, 40, 30, 110, 50);
+        let capture = DynamicImage::ImageLuma8(capture);
+
+        let restored = |degrees: f32| {
+            let orientation = Orientation::Rotated(degrees);
+            let turned = orientation.apply(&capture);
+            let mut doc = OcrDocument::new("test", turned.width(), turned.height());
+            doc.lines.push(line("box", ink_bounds(&turned, 0, turned.height())));
+            orientation.restore(&mut doc, width, height);
+            assert_eq!((doc.width, doc.height, doc.rotation), (width, height, degrees));
+            polygon_bounds(&doc.lines[0].polygon).unwrap()
+        };
+
+        // Quarter turns are exact
+        for degrees in [90.0, -90.0] {
+            assert_close(restored(degrees), (40.0, 30.0, 110.0, 50.0), 0.01);
+        }
+        // Any other angle boxes the tilted rectangle, so only the centre maps
+        // straight back
+        for degrees in [8.0, -8.0] {
+            let (x0, y0, x1, y1) = restored(degrees);
+            let centre = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+            assert!((centre.0 - 75.0).abs() <= 1.0 && (centre.1 - 40.0).abs() <= 1.0, "{}°: centre {:?}", degrees, centre);
+        }
+    }
+
+    #[test]
+    fn columns_round_trip() {
+        // Two columns of three glyphs, read right to left
+        let mut capture = blank(260, 160);
+        for x0 in [180, 120] {
+            for y0 in [20, 60, 100] {
+                fill(&mut capture, x0 + 4, y0, x0 + 26, y0 + 8);
+                fill(&mut capture, x0 + 12, y0, x0 + 18, y0 + 30);
+            }
+        }
+        let threshold = otsu_threshold(&capture);
+        let columns = find_columns(&capture, threshold, ink_is_dark(&capture, threshold));
+        assert_eq!(columns.len(), 2);
+        assert!(looks_vertical(&columns));
+        assert_eq!(columns[1], Column { x0: 184, x1: 206, y0: 20, y1: 130, cells: vec![(20, 50), (60, 90), (100, 130)] });
+
+        // Each column becomes one row, rightmost first
+        let rebuilt = rebuild_columns(&DynamicImage::ImageLuma8(capture), &columns);
+        let row_h = row_height(&columns);
+        assert_eq!(rebuilt.height(), 2 * row_h);
+        let lines = vec![line("左列", ink_bounds(&rebuilt, row_h, 2 * row_h)), line("右列", ink_bounds(&rebuilt, 0, row_h))];
+
+        let restored = restore_columns(lines, &columns);
+        assert_eq!(restored.iter().map(|l| l.text.as_str()).collect::<Vec<_>>(), ["右列", "左列"]);
+        assert_close(polygon_bounds(&restored[0].polygon).unwrap(), (184.0, 20.0, 206.0, 130.0), 0.0);
+        assert_close(polygon_bounds(&restored[1].polygon).unwrap(), (124.0, 20.0, 146.0, 130.0), 0.0);
+    }
+}
@@ -0,0 +1,328 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage, Luma};
+use serde::{Deserialize, Serialize};
+
+/// Resampling filter for `Scale`, mirroring `image::imageops::FilterType`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    #[default]
+    Lanczos3,
+}
+
+impl From<ScaleFilter> for FilterType {
+    fn from(filter: ScaleFilter) -> Self {
+        match filter {
+            ScaleFilter::Nearest => FilterType::Nearest,
+            ScaleFilter::Triangle => FilterType::Triangle,
+            ScaleFilter::CatmullRom => FilterType::CatmullRom,
+            ScaleFilter::Gaussian => FilterType::Gaussian,
+            ScaleFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// One stage of the pipeline run on a capture before it reaches an engine.
+/// Stored in `AppConfig::ocr_preprocess` as e.g. `{"op": "scale", "factor": 2.5}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PreprocessStep {
+    /// Resize by `factor`. Small UI fonts recognize much better at 2-3x.
+    Scale {
+        factor: f32,
+        #[serde(default)]
+        filter: ScaleFilter,
+    },
+    Grayscale,
+    /// Local mean thresholding over a `(2 * radius + 1)²` window. A pixel turns
+    /// black when it is more than `offset` below its neighbourhood mean.
+    Binarize {
+        #[serde(default = "default_binarize_radius")]
+        radius: u32,
+        #[serde(default = "default_binarize_offset")]
+        offset: i32,
+    },
+    /// Maps the `low`..`high` luminance percentiles to the full 0..255 range.
+    ContrastStretch {
+        #[serde(default = "default_stretch_low")]
+        low: f32,
+        #[serde(default = "default_stretch_high")]
+        high: f32,
+    },
+    /// Unsharp mask.
+    Sharpen {
+        #[serde(default = "default_sharpen_sigma")]
+        sigma: f32,
+        #[serde(default)]
+        threshold: i32,
+    },
+    Invert,
+    /// Median filter; removes speckle without blurring glyph edges much.
+    Denoise {
+        #[serde(default = "default_denoise_radius")]
+        radius: u32,
+    },
+}
+
+fn default_binarize_radius() -> u32 {
+    15
+}
+
+fn default_binarize_offset() -> i32 {
+    10
+}
+
+fn default_stretch_low() -> f32 {
+    0.01
+}
+
+fn default_stretch_high() -> f32 {
+    0.99
+}
+
+fn default_sharpen_sigma() -> f32 {
+    1.0
+}
+
+fn default_denoise_radius() -> u32 {
+    1
+}
+
+/// Output of `apply`. `scale` is the product of all `Scale` steps, so engine
+/// coordinates can be divided by it to get back to the input image.
+pub struct Prepared {
+    pub image: DynamicImage,
+    pub scale: f32,
+}
+
+pub fn apply(image: &DynamicImage, steps: &[PreprocessStep]) -> Prepared {
+    let mut image = image.clone();
+    let mut scale = 1.0f32;
+
+    for step in steps {
+        image = match *step {
+            PreprocessStep::Scale { factor, filter } => {
+                if factor <= 0.0 || (factor - 1.0).abs() < f32::EPSILON {
+                    continue;
+                }
+                let w = ((image.width() as f32 * factor).round() as u32).max(1);
+                let h = ((image.height() as f32 * factor).round() as u32).max(1);
+                scale *= factor;
+                image.resize_exact(w, h, filter.into())
+            }
+            PreprocessStep::Grayscale => DynamicImage::ImageLuma8(image.to_luma8()),
+            PreprocessStep::Binarize { radius, offset } => DynamicImage::ImageLuma8(binarize(&image.to_luma8(), radius, offset)),
+            PreprocessStep::ContrastStretch { low, high } => contrast_stretch(image, low, high),
+            PreprocessStep::Sharpen { sigma, threshold } => image.unsharpen(sigma, threshold),
+            PreprocessStep::Invert => {
+                image.invert();
+                image
+            }
+            PreprocessStep::Denoise { radius } => denoise(&image, radius),
+        };
+    }
+
+    Prepared { image, scale }
+}
+
+/// Adaptive mean threshold using an integral image, so the cost does not
+/// depend on the window size.
+fn binarize(gray: &GrayImage, radius: u32, offset: i32) -> GrayImage {
+    let (w, h) = gray.dimensions();
+    let stride = w as usize + 1;
+    let mut integral = vec![0u64; stride * (h as usize + 1)];
+    for y in 0..h as usize {
+        let mut row = 0u64;
+        for x in 0..w as usize {
+            row += gray.get_pixel(x as u32, y as u32).0[0] as u64;
+            integral[(y + 1) * stride + x + 1] = integral[y * stride + x + 1] + row;
+        }
+    }
+
+    let r = radius.max(1) as i64;
+    GrayImage::from_fn(w, h, |x, y| {
+        let x0 = (x as i64 - r).max(0) as usize;
+        let y0 = (y as i64 - r).max(0) as usize;
+        let x1 = (x as i64 + r + 1).min(w as i64) as usize;
+        let y1 = (y as i64 + r + 1).min(h as i64) as usize;
+        let sum = integral[y1 * stride + x1] + integral[y0 * stride + x0] - integral[y0 * stride + x1] - integral[y1 * stride + x0];
+        let mean = (sum / ((x1 - x0) * (y1 - y0)) as u64) as i32;
+        let v = gray.get_pixel(x, y).0[0] as i32;
+        Luma([if v < mean - offset { 0 } else { 255 }])
+    })
+}
+
+fn contrast_stretch(image: DynamicImage, low: f32, high: f32) -> DynamicImage {
+    let gray = image.to_luma8();
+    let mut histogram = [0u64; 256];
+    for p in gray.pixels() {
+        histogram[p.0[0] as usize] += 1;
+    }
+
+    let total = gray.pixels().len() as f32;
+    let percentile = |q: f32| {
+        let target = (q.clamp(0.0, 1.0) * total) as u64;
+        let mut seen = 0;
+        for (value, count) in histogram.iter().enumerate() {
+            seen += count;
+            if seen > target {
+                return value as u8;
+            }
+        }
+        255
+    };
+    let (lo, hi) = (percentile(low), percentile(high));
+    if hi <= lo {
+        return image;
+    }
+
+    let lut: Vec<u8> = (0..=255u32)
+        .map(|v| ((v.saturating_sub(lo as u32) * 255) / (hi - lo) as u32).min(255) as u8)
+        .collect();
+
+    // Stretch every color channel with the luminance curve so hues survive
+    match image {
+        DynamicImage::ImageLuma8(mut img) => {
+            img.pixels_mut().for_each(|p| p.0[0] = lut[p.0[0] as usize]);
+            DynamicImage::ImageLuma8(img)
+        }
+        other => {
+            let mut img = other.to_rgba8();
+            for p in img.pixels_mut() {
+                for c in &mut p.0[..3] {
+                    *c = lut[*c as usize];
+                }
+            }
+            DynamicImage::ImageRgba8(img)
+        }
+    }
+}
+
+fn denoise(image: &DynamicImage, radius: u32) -> DynamicImage {
+    if radius == 0 {
+        return image.clone();
+    }
+    match image {
+        DynamicImage::ImageLuma8(img) => {
+            let (w, h) = img.dimensions();
+            DynamicImage::ImageLuma8(GrayImage::from_raw(w, h, median(img.as_raw(), w, h, 1, radius)).unwrap())
+        }
+        other => {
+            let img = other.to_rgba8();
+            let (w, h) = img.dimensions();
+            DynamicImage::ImageRgba8(image::RgbaImage::from_raw(w, h, median(img.as_raw(), w, h, 4, radius)).unwrap())
+        }
+    }
+}
+
+/// Per-channel median over a square window, clamped at the borders.
+fn median(data: &[u8], w: u32, h: u32, channels: usize, radius: u32) -> Vec<u8> {
+    let (w, h, r) = (w as i64, h as i64, radius as i64);
+    let mut out = vec![0u8; data.len()];
+    let mut window = Vec::with_capacity(((2 * r + 1) * (2 * r + 1)) as usize);
+
+    for y in 0..h {
+        for x in 0..w {
+            for c in 0..channels {
+                window.clear();
+                for dy in -r..=r {
+                    let sy = (y + dy).clamp(0, h - 1);
+                    for dx in -r..=r {
+                        let sx = (x + dx).clamp(0, w - 1);
+                        window.push(data[((sy * w + sx) as usize) * channels + c]);
+                    }
+                }
+                let mid = window.len() / 2;
+                let (_, m, _) = window.select_nth_unstable(mid);
+                out[((y * w + x) as usize) * channels + c] = *m;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(image: &GrayImage, y: u32) -> Vec<u8> {
+        (0..image.width()).map(|x| image.get_pixel(x, y).0[0]).collect()
+    }
+
+    #[test]
+    fn binarize_leaves_uniform_areas_white() {
+        let flat = GrayImage::from_pixel(12, 6, Luma([128]));
+        assert!(binarize(&flat, 3, 10).pixels().all(|p| p.0[0] == 255));
+    }
+
+    #[test]
+    fn binarize_marks_the_dark_side_of_an_edge() {
+        // Dark on the left, light on the right. Only dark pixels whose window
+        // reaches the light side are far enough below their local mean.
+        let step = GrayImage::from_fn(20, 5, |x, _| Luma([if x < 10 { 40 } else { 200 }]));
+        let binary = binarize(&step, 3, 10);
+        let expected: Vec<u8> = (0..20).map(|x| if (7..10).contains(&x) { 0 } else { 255 }).collect();
+        for y in 0..5 {
+            assert_eq!(row(&binary, y), expected);
+        }
+    }
+
+    #[test]
+    fn contrast_stretch_clips_outlier_percentiles() {
+        // 60..=157 plus one black and one white outlier, 100 pixels in all
+        let mut values: Vec<u8> = (60..=157).collect();
+        values.extend([0, 255]);
+        let gray = GrayImage::from_raw(10, 10, values).unwrap();
+
+        let stretched = contrast_stretch(DynamicImage::ImageLuma8(gray), 0.01, 0.98).to_luma8();
+        let out = stretched.as_raw();
+        assert_eq!(out[0], 0); // 60, the 1st percentile
+        assert_eq!(out[48], 126); // 108
+        assert_eq!(out[97], 255); // 157, the 98th percentile
+        assert_eq!(&out[98..], [0, 255]);
+    }
+
+    #[test]
+    fn contrast_stretch_leaves_flat_images_alone() {
+        let flat = DynamicImage::ImageLuma8(GrayImage::from_pixel(4, 4, Luma([90])));
+        assert_eq!(contrast_stretch(flat.clone(), 0.01, 0.99).to_luma8().as_raw(), flat.to_luma8().as_raw());
+    }
+
+    #[test]
+    fn denoise_removes_salt_and_pepper_but_keeps_strokes() {
+        let mut gray = GrayImage::from_fn(9, 9, |x, _| Luma([if (3..6).contains(&x) { 0 } else { 200 }]));
+        gray.put_pixel(7, 1, Luma([255]));
+        gray.put_pixel(1, 7, Luma([0]));
+        gray.put_pixel(0, 0, Luma([0]));
+
+        let clean = denoise(&DynamicImage::ImageLuma8(gray), 1).to_luma8();
+        assert_eq!(clean.get_pixel(7, 1).0[0], 200);
+        assert_eq!(clean.get_pixel(1, 7).0[0], 200);
+        assert_eq!(clean.get_pixel(0, 0).0[0], 200);
+        for y in 0..9 {
+            assert_eq!(row(&clean, y), [200, 200, 200, 0, 0, 0, 200, 200, 200]);
+        }
+    }
+
+    #[test]
+    fn apply_reports_the_combined_scale() {
+        let image = DynamicImage::ImageLuma8(GrayImage::new(10, 8));
+        let steps = [
+            PreprocessStep::Scale { factor: 2.0, filter: ScaleFilter::Triangle },
+            PreprocessStep::Grayscale,
+            // Ignored: a no-op and an invalid factor
+            PreprocessStep::Scale { factor: 1.0, filter: ScaleFilter::Nearest },
+            PreprocessStep::Scale { factor: -2.0, filter: ScaleFilter::Nearest },
+            PreprocessStep::Scale { factor: 1.5, filter: ScaleFilter::Nearest },
+        ];
+        let prepared = apply(&image, &steps);
+        assert_eq!(prepared.scale, 3.0);
+        assert_eq!((prepared.image.width(), prepared.image.height()), (30, 24));
+
+        assert_eq!(apply(&image, &[]).scale, 1.0);
+    }
+}
@@ -1,6 +1,21 @@
 mod ocr_core;
+mod capture_coords;
+mod barcode;
+mod ocr_backend;
+mod ocr_document;
+mod ocr_scoring;
+mod image_preprocess;
+mod image_orientation;
+mod ocr_layout;
+mod ocr_table;
+mod text_normalize;
 mod translate;
 mod paddle_ocr_engine;
+mod tesseract_ocr_engine;
+mod window_snap;
+mod scroll_capture;
+#[cfg(target_os = "windows")]
+mod windows_ocr_engine;
 mod config;
 
 use config::{ConfigState, get_config, AppConfig};
@@ -22,6 +37,8 @@ struct AppState {
     is_capturing: Mutex<bool>,
     is_processing: Mutex<bool>,
     pin_cache: Mutex<std::collections::HashMap<String, String>>,
+    /// Result of the latest selection OCR, kept for `export_table`.
+    last_document: Mutex<Option<ocr_document::OcrDocument>>,
 }
 
 #[tauri::command]
@@ -33,71 +50,174 @@ fn toggle_pin(state: State<AppState>) -> bool {
 }
 
 #[tauri::command]
-async fn capture_full_screen() -> Result<String, String> {
-    ocr_core::capture_full_screen().await
+async fn capture_full_screen(app: tauri::AppHandle, config_state: State<'_, ConfigState>) -> Result<u64, String> {
+    let scope = config_state.config.lock().unwrap().capture_scope.clone();
+    let monitors = capture_monitors(&app, &scope)?;
+    // Before the overlay goes up and covers everything
+    window_snap::refresh();
+    ocr_core::capture_full_screen(&monitors).await
+}
+
+/// Serves `booboo://capture/<id>`: the cached screenshot as BMP, from memory.
+/// The overlay loads its background this way instead of from a temp file.
+fn capture_protocol(request: &tauri::http::Request<Vec<u8>>) -> tauri::http::Response<Vec<u8>> {
+    use tauri::http::{header, Response, StatusCode};
+
+    // The webview escapes the id's slash on some platforms
+    let path = urlencoding::decode(request.uri().path()).map(|p| p.into_owned()).unwrap_or_default();
+    let bmp = path
+        .trim_start_matches('/')
+        .strip_prefix("capture/")
+        .and_then(|id| id.parse().ok())
+        .and_then(ocr_core::cached_capture_bmp);
+    match bmp {
+        Some(bytes) => Response::builder()
+            .header(header::CONTENT_TYPE, "image/bmp")
+            .header(header::CACHE_CONTROL, "no-store")
+            .body(bytes)
+            .unwrap(),
+        None => {
+            println!("capture_protocol: No capture for {}", path);
+            Response::builder().status(StatusCode::NOT_FOUND).body(Vec::new()).unwrap()
+        }
+    }
+}
+
+/// Windows and controls on screen when the last screenshot was taken, in
+/// physical desktop pixels, for the overlay to snap selections to.
+#[tauri::command]
+fn get_window_rects() -> Vec<window_snap::SnapWindow> {
+    window_snap::snapshot()
+}
+
+/// The monitors a capture covers: the one under the cursor, or every monitor
+/// when `scope` is "all".
+fn capture_monitors(app: &tauri::AppHandle, scope: &str) -> Result<Vec<ocr_core::MonitorInfo>, String> {
+    let monitors: Vec<ocr_core::MonitorInfo> = app
+        .available_monitors()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|m| ocr_core::MonitorInfo {
+            name: m.name().cloned().unwrap_or_default(),
+            x: m.position().x,
+            y: m.position().y,
+            width: m.size().width,
+            height: m.size().height,
+            scale_factor: m.scale_factor(),
+        })
+        .collect();
+    if monitors.is_empty() {
+        return Err("No monitor found".to_string());
+    }
+    if scope == "all" {
+        return Ok(monitors);
+    }
+
+    let cursor = app.cursor_position().map_err(|e| e.to_string())?;
+    let monitor = ocr_core::monitor_at(&monitors, cursor.x.round() as i32, cursor.y.round() as i32).cloned();
+    Ok(monitor.into_iter().collect())
 }
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
-async fn ocr_capture_area(window: tauri::Window, config_state: State<'_, ConfigState>, x: i32, y: i32, width: u32, height: u32) -> Result<String, String> {
+async fn ocr_capture_area(window: tauri::Window, config_state: State<'_, ConfigState>, x: i32, y: i32, width: u32, height: u32, mode: Option<String>) -> Result<String, String> {
+    let (doc, _) = recognize_selection(window, config_state, x, y, width, height, mode).await?;
+    Ok(capture_text(&doc))
+}
+
+/// Text shown for a capture: the reading text, then any decoded codes not
+/// already in it, one payload per line.
+fn capture_text(doc: &ocr_document::OcrDocument) -> String {
+    let mut text = doc.reading_text();
+    for code in doc.codes.iter().filter(|c| !doc.lines.iter().any(|l| l.text == c.text)) {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&code.text);
+    }
+    text
+}
+
+/// Like `ocr_capture_area`, but returns the full `OcrDocument` with boxes
+/// mapped into the overlay's logical coordinates so they can be drawn directly.
+#[tauri::command]
+async fn ocr_capture_area_detailed(window: tauri::Window, config_state: State<'_, ConfigState>, x: i32, y: i32, width: u32, height: u32, mode: Option<String>) -> Result<ocr_document::OcrDocument, String> {
+    let (mut doc, mapping) = recognize_selection(window, config_state, x, y, width, height, mode).await?;
+    let (scale, offset_x, offset_y) = mapping.crop_to_overlay();
+    doc.transform(scale, offset_x, offset_y);
+    Ok(doc)
+}
+
+/// Maps an overlay selection onto the cached screenshot, using the overlay's
+/// own DPI and `padding` logical pixels of margin.
+fn map_selection(window: &tauri::Window, x: i32, y: i32, width: u32, height: u32, padding: f64) -> Result<capture_coords::SelectionMapping, String> {
     let scale_factor = window.scale_factor().map_err(|e| e.to_string())?;
-    let window_pos = window.outer_position().map_err(|e| e.to_string())?;
-    println!("Trace 1: Window outer_pos: {:?}, Scale: {}, Selection: logical x={}, y={}, w={}, h={}", 
-        window_pos, scale_factor, x, y, width, height);
+    let geometry = ocr_core::capture_geometry().ok_or("No cached screenshot found. Please capture full screen first.")?;
+    let selection = capture_coords::LogicalRect { x: x as f64, y: y as f64, width: width as f64, height: height as f64 };
+    let mapping = capture_coords::map_selection(&geometry, scale_factor, selection, padding)
+        .ok_or("Selection is outside the captured screen")?;
+    println!("map_selection: logical {:?} at scale {} -> capture pixels {:?} (desktop {:?})",
+        selection, scale_factor, mapping.crop, mapping.desktop_origin(&geometry));
+    Ok(mapping)
+}
 
+/// Crops the selection from the cached screenshot and runs OCR on it.
+/// `mode` is "code" for code captures, "decode" for QR codes and barcodes,
+/// anything else (or none) for text.
+/// Returns the document together with where its crop came from.
+async fn recognize_selection(window: tauri::Window, config_state: State<'_, ConfigState>, x: i32, y: i32, width: u32, height: u32, mode: Option<String>) -> Result<(ocr_document::OcrDocument, capture_coords::SelectionMapping), String> {
+    // A margin around the selection gives the engines some context
+    let padding = config_state.config.lock().unwrap().ocr_capture_padding;
+    let mapping = map_selection(&window, x, y, width, height, padding)?;
+    let doc = recognize_crop(&window, config_state, mapping.crop, mode).await?;
+    Ok((doc, mapping))
+}
+
+/// Runs OCR on `crop` of the cached screenshot and keeps the result for
+/// `export_table`.
+async fn recognize_crop(window: &tauri::Window, config_state: State<'_, ConfigState>, crop: capture_coords::PixelRect, mode: Option<String>) -> Result<ocr_document::OcrDocument, String> {
     // Get current config
     let config = config_state.config.lock().unwrap().clone();
+    let mode = ocr_core::OcrMode::from_name(mode.as_deref());
 
+    println!("Trace 3: Calling capture_area (crop from cache)...");
+    let img = ocr_core::capture_area(crop).await.map_err(|e| format!("Capture failed: {}", e))?;
+
+    // Engines block (and may hang until their timeout); keep them off the async runtime
     let res = tauri::async_runtime::spawn_blocking(move || {
         std::panic::catch_unwind(move || {
-            // Absolute Screen Physical = Selection Logical * Scale
-            // (Since the capture and overlay are both screen-fixed in this new mode)
-            let abs_x_raw = (x as f64 * scale_factor).round() as i32;
-            let abs_y_raw = (y as f64 * scale_factor).round() as i32;
-            let abs_w_raw = (width as f64 * scale_factor).round() as u32;
-            let abs_h_raw = (height as f64 * scale_factor).round() as u32;
-
-            // Add padding to help OCR context (e.g. 15px visual margin)
-            // But verify we don't go negative on x/y. 
-            // Width/height clamping happens in ocr_core::capture_area automatically.
-            let padding = (15.0 * scale_factor).round() as i32;
-            
-            let abs_x = (abs_x_raw - padding).max(0);
-            let abs_y = (abs_y_raw - padding).max(0);
-            
-            // Adjust width/height to compensate for the shift + extra margin on right/bottom
-            // Total width increase = padding_left + padding_right
-            // Total height increase = padding_top + padding_bottom
-            // Note: If x was 0, padding_left was partly ignored, but we still add full padding_right?
-            // Let's keep it simple: Expand outward.
-            let abs_w = abs_w_raw + (2 * padding as u32);
-            let abs_h = abs_h_raw + (2 * padding as u32);
-
-            println!("Trace 2: Final Absolute Screen Physical: x={}, y={}, w={}, h={}", abs_x, abs_y, abs_w, abs_h);
-            
-            tauri::async_runtime::block_on(async {
-                println!("Trace 3: Calling capture_area (crop from cache)...");
-                let img = match ocr_core::capture_area(abs_x, abs_y, abs_w, abs_h).await {
-                    Ok(i) => i,
-                    Err(e) => return Err(format!("Capture failed: {}", e)),
-                };
-                
-                println!("Trace 4: Calling run_ocr with engine: {}...", config.ocr_engine);
-                match ocr_core::run_ocr(img, &config).await {
-                    Ok(t) => Ok(t),
-                    Err(e) => Err(format!("OCR failed: {}", e)),
-                }
-            })
+            println!("Trace 4: Calling run_ocr with engine: {}...", config.ocr_engine);
+            ocr_core::run_ocr(img, &config, mode).map_err(|e| format!("OCR failed: {}", e))
         })
     }).await.map_err(|e| format!("JoinError: {}", e))?;
 
-    match res {
-        Ok(r) => r,
+    let doc = match res {
+        Ok(r) => r?,
         Err(e) => {
             println!("Trace ERROR: {:?}", e);
-            Err("Command panicked during execution".to_string())
+            return Err("Command panicked during execution".to_string());
         }
+    };
+    *window.state::<AppState>().last_document.lock().unwrap() = Some(doc.clone());
+    Ok(doc)
+}
+
+/// Formats the table found in the last recognized selection as TSV, CSV or
+/// Markdown, optionally copying it to the clipboard.
+#[tauri::command]
+fn export_table(state: State<AppState>, format: String, copy: bool) -> Result<String, String> {
+    let format = ocr_table::TableFormat::from_name(&format)?;
+    let doc = state.last_document.lock().unwrap().clone().ok_or("Nothing has been recognized yet")?;
+    let table = ocr_table::detect(&doc).ok_or("No table found in the last capture")?;
+    println!("export_table: {} rows x {} columns", table.rows.len(), table.rows[0].len());
+
+    let text = table.format(format);
+    if copy {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text.clone()))
+            .map_err(|e| format!("Clipboard error: {}", e))?;
     }
+    Ok(text)
 }
 
 #[tauri::command]
@@ -129,6 +249,25 @@ async fn verify_youdao_id_and_key(app_key: String, app_secret: String) -> Result
     }
 }
 
+#[tauri::command]
+async fn verify_paddle_engine(paddle_engine_path: String, paddle_models_dir: String, paddle_config_path: String, paddle_extra_args: Vec<String>) -> Result<String, String> {
+    let mut config = config::AppConfig::default();
+    config.paddle_engine_path = paddle_engine_path;
+    config.paddle_models_dir = paddle_models_dir;
+    config.paddle_config_path = paddle_config_path;
+    config.paddle_extra_args = paddle_extra_args;
+
+    // Spawning and loading models blocks for a while
+    let res = tauri::async_runtime::spawn_blocking(move || paddle_ocr_engine::test_engine(&config))
+        .await
+        .map_err(|e| format!("JoinError: {}", e))?;
+
+    match res {
+        Ok(_) => Ok("验证成功！PaddleOCR 引擎运行正常。".to_string()),
+        Err(e) => Err(format!("验证失败: {}", e)),
+    }
+}
+
 #[tauri::command]
 fn log_message(msg: String) {
     println!("FRONTEND LOG: {}", msg);
@@ -148,8 +287,12 @@ fn enter_capture_mode(window: tauri::Window, state: State<'_, AppState>) {
     
     let t1 = start.elapsed();
     
-    // 2. Exact Monitor Size: Back to full screen for a better UX (no gaps).
-    if let Ok(Some(monitor)) = window.current_monitor() {
+    // 2. Cover exactly what was captured: the monitor under the cursor or the
+    // whole virtual desktop.
+    if let Some(geometry) = ocr_core::capture_geometry() {
+        let _ = window.set_position(tauri::PhysicalPosition::new(geometry.x, geometry.y));
+        let _ = window.set_size(tauri::PhysicalSize::new(geometry.width, geometry.height));
+    } else if let Ok(Some(monitor)) = window.current_monitor() {
         let size = monitor.size();
         let position = monitor.position();
         
@@ -230,21 +373,16 @@ async fn create_pin_window(app: tauri::AppHandle, label: String, width: f64, hei
 
 #[tauri::command]
 async fn pin_selection(app: tauri::AppHandle, window: tauri::Window, x: i32, y: i32, width: u32, height: u32) -> Result<(), String> {
-    let scale_factor = window.scale_factor().map_err(|e| e.to_string())?;
-    
-    // 1. Capture the area
-    // Reuse logic from ocr_capture_area but for pinning
-    // We need absolute physical coordinates
-    
-    let abs_x = (x as f64 * scale_factor).round() as i32;
-    let abs_y = (y as f64 * scale_factor).round() as i32;
-    let abs_w = (width as f64 * scale_factor).round() as u32;
-    let abs_h = (height as f64 * scale_factor).round() as u32;
-    
-    let img = ocr_core::capture_area(abs_x, abs_y, abs_w, abs_h).await.map_err(|e| e.to_string())?;
-    
+    // 1. Capture the area, exactly as selected
+    let mapping = map_selection(&window, x, y, width, height, 0.0)?;
+    let img = ocr_core::capture_area(mapping.crop).await.map_err(|e| e.to_string())?;
+    pin_image(app, &img, width as f64, height as f64).await
+}
+
+/// Opens a pin window of `width` x `height` logical pixels showing `img`.
+async fn pin_image(app: tauri::AppHandle, img: &image::DynamicImage, width: f64, height: f64) -> Result<(), String> {
     // 2. Convert to base64
-    let base64_img = ocr_core::image_to_base64(&img);
+    let base64_img = ocr_core::image_to_base64(img);
     if base64_img.is_empty() {
         return Err("Failed to encode image".to_string());
     }
@@ -256,7 +394,7 @@ async fn pin_selection(app: tauri::AppHandle, window: tauri::Window, x: i32, y:
     let label = format!("pin_{}", timestamp);
     
     // Use logical size for window creation
-    create_pin_window(app.clone(), label.clone(), width as f64, height as f64).await?;
+    create_pin_window(app.clone(), label.clone(), width, height).await?;
     
     // 4. Store image data in cache
     {
@@ -268,6 +406,56 @@ async fn pin_selection(app: tauri::AppHandle, window: tauri::Window, x: i32, y:
     Ok(())
 }
 
+/// Starts a scrolling capture of an overlay selection: the region is captured
+/// live while the user scrolls and the frames are stitched, until
+/// `stop_scroll_capture`. Progress is emitted as "scroll-capture-progress".
+#[tauri::command]
+fn start_scroll_capture(app: tauri::AppHandle, window: tauri::Window, x: i32, y: i32, width: u32, height: u32) -> Result<(), String> {
+    let mapping = map_selection(&window, x, y, width, height, 0.0)?;
+    let geometry = ocr_core::capture_geometry().ok_or("No cached screenshot found. Please capture full screen first.")?;
+    let (left, top) = mapping.desktop_origin(&geometry);
+    let monitor = geometry
+        .monitor_at(left + mapping.crop.width as i32 / 2, top + mapping.crop.height as i32 / 2)
+        .cloned()
+        .ok_or("No monitor found")?;
+    scroll_capture::start(monitor, left, top, mapping.crop.width, mapping.crop.height, move |progress| {
+        let _ = app.emit("scroll-capture-progress", progress);
+    })
+}
+
+/// Stops the scrolling capture and caches the stitched image in place of the
+/// screenshot, for `ocr_last_capture` and `pin_last_capture`. Returns its
+/// size in physical pixels.
+#[tauri::command]
+fn stop_scroll_capture() -> Result<(u32, u32), String> {
+    scroll_capture::stop()
+}
+
+/// Recognizes the whole cached capture, e.g. a stitched scrolling capture.
+#[tauri::command]
+async fn ocr_last_capture(window: tauri::Window, config_state: State<'_, ConfigState>, mode: Option<String>) -> Result<String, String> {
+    let geometry = ocr_core::capture_geometry().ok_or("No cached screenshot found. Please capture full screen first.")?;
+    let crop = capture_coords::PixelRect { x: 0, y: 0, width: geometry.width, height: geometry.height };
+    let doc = recognize_crop(&window, config_state, crop, mode).await?;
+    Ok(capture_text(&doc))
+}
+
+/// Pins the whole cached capture, shrunk to fit the monitor it came from.
+#[tauri::command]
+async fn pin_last_capture(app: tauri::AppHandle) -> Result<(), String> {
+    let geometry = ocr_core::capture_geometry().ok_or("No cached screenshot found. Please capture full screen first.")?;
+    let monitor = geometry.monitor_at(geometry.x, geometry.y).ok_or("No monitor found")?;
+    let crop = capture_coords::PixelRect { x: 0, y: 0, width: geometry.width, height: geometry.height };
+    let img = ocr_core::capture_area(crop).await?;
+
+    let width = geometry.width as f64 / monitor.scale_factor;
+    let height = geometry.height as f64 / monitor.scale_factor;
+    // A long page would not fit; the pin shows it scaled down instead
+    let max_height = monitor.height as f64 / monitor.scale_factor * 0.8;
+    let fit = (max_height / height).min(1.0);
+    pin_image(app, &img, width * fit, height * fit).await
+}
+
 #[tauri::command]
 async fn get_pin_image(state: State<'_, AppState>, label: String) -> Result<String, String> {
     let mut cache = state.pin_cache.lock().unwrap();
@@ -363,14 +551,33 @@ pub fn run() {
             })
             .build()
         )
+        .register_asynchronous_uri_scheme_protocol("booboo", |_ctx, request, responder| {
+            // Encoding a full-desktop BMP takes a moment; keep it off the webview's thread
+            std::thread::spawn(move || responder.respond(capture_protocol(&request)));
+        })
         // ... (rest of setup)
         .setup(|app| {
+            // Captures used to be handed over as a temp file; remove any left behind
+            let _ = std::fs::remove_file(std::env::temp_dir().join("booboo_capture.bmp"));
+
             // Initialize Config State
             let config_state = ConfigState::new(app.handle());
             app.manage(config_state);
 
+            ocr_backend::register_builtin_backends();
+
             // Register global shortcut
             let config = app.state::<ConfigState>().config.lock().unwrap().clone();
+
+            // Load the selected engine's models now instead of on the first capture
+            if let Some(backend) = ocr_backend::find_backend(&config.ocr_engine) {
+                let warm_config = config.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = backend.warm_up(&warm_config) {
+                        println!("OCR warm-up for '{}' failed: {}", backend.name(), e);
+                    }
+                });
+            }
             let shortcut_str = if config.shortcut.is_empty() { "Alt+Shift+A".to_string() } else { config.shortcut.clone() };
             let pin_str = if config.shortcut_pin.is_empty() { "Alt+Shift+S".to_string() } else { config.shortcut_pin.clone() };
 
@@ -445,6 +652,7 @@ pub fn run() {
                 .on_menu_event(|app, event| {
                     match event.id.as_ref() {
                         "quit" => {
+                            ocr_backend::shutdown_all();
                             app.exit(0);
                         }
                         "settings" => {
@@ -475,6 +683,7 @@ pub fn run() {
                 is_capturing: Mutex::new(false),
                 is_processing: Mutex::new(false),
                 pin_cache: Mutex::new(std::collections::HashMap::new()),
+                last_document: Mutex::new(None),
             });
 
             // Handle window events
@@ -527,12 +736,23 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             ocr_capture_area, 
+            ocr_capture_area_detailed,
+            export_table,
             translate_text, 
             capture_full_screen, 
+            get_window_rects,
+            start_scroll_capture,
+            stop_scroll_capture,
+            ocr_last_capture,
+            pin_last_capture,
             toggle_pin,
             get_config,
             save_config,
+            ocr_backend::list_ocr_backends,
+            #[cfg(target_os = "windows")]
+            windows_ocr_engine::list_windows_ocr_languages,
             verify_youdao_id_and_key,
+            verify_paddle_engine,
             resize_dashboard_window,
             log_message,
             set_processing_state, // Added
@@ -543,8 +763,13 @@ pub fn run() {
             get_pin_image, // Added
             close_window  // New command
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                ocr_backend::shutdown_all();
+            }
+        });
 }
 
 #[tauri::command]
@@ -587,5 +812,6 @@ fn save_config(app: tauri::AppHandle, state: State<ConfigState>, new_config: App
     
     *config = new_config;
     drop(config); // unlock before saving to file
+    tesseract_ocr_engine::clear_language_cache();
     state.save()
 }
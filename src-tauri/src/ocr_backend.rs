@@ -0,0 +1,120 @@
+use image::DynamicImage;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+
+use crate::config::AppConfig;
+use crate::ocr_document::OcrDocument;
+
+/// What an engine can give back besides plain text.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct OcrCapabilities {
+    pub bounding_boxes: bool,
+    pub confidence: bool,
+    pub language_selection: bool,
+    pub persistent_process: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "snake_case")]
+pub enum BackendHealth {
+    Ready,
+    Degraded(String),
+    Unavailable(String),
+}
+
+/// A recognition engine. Implementations are registered once at startup and
+/// shared between threads, so any per-engine state needs its own locking.
+pub trait OcrBackend: Send + Sync {
+    /// Stable identifier used in `AppConfig::ocr_engine` / `ocr_fallback`.
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> OcrCapabilities;
+
+    /// BCP-47 tags the engine can recognize with the current config.
+    fn supported_languages(&self, config: &AppConfig) -> Vec<String>;
+
+    /// Cheap readiness probe. Must not run a recognition.
+    fn health(&self, config: &AppConfig) -> BackendHealth;
+
+    /// Blocking recognition. Callers run this off the async runtime.
+    fn recognize(&self, image: &DynamicImage, config: &AppConfig) -> Result<OcrDocument, String>;
+
+    /// Loads models ahead of the first capture. Blocking; called from a
+    /// background thread at startup for the selected engine only.
+    fn warm_up(&self, _config: &AppConfig) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Releases processes or other resources. Called on quit and app exit.
+    fn shutdown(&self) {}
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackendInfo {
+    pub name: String,
+    pub capabilities: OcrCapabilities,
+    pub languages: Vec<String>,
+    pub health: BackendHealth,
+}
+
+static REGISTRY: Lazy<RwLock<Vec<Arc<dyn OcrBackend>>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Adds a backend, replacing any previously registered one with the same name.
+pub fn register_backend(backend: Arc<dyn OcrBackend>) {
+    let mut registry = REGISTRY.write().unwrap();
+    registry.retain(|b| b.name() != backend.name());
+    println!("ocr_backend: registered '{}'", backend.name());
+    registry.push(backend);
+}
+
+pub fn register_builtin_backends() {
+    register_backend(Arc::new(crate::paddle_ocr_engine::PaddleBackend));
+    register_backend(Arc::new(crate::tesseract_ocr_engine::TesseractBackend));
+    #[cfg(target_os = "windows")]
+    register_backend(Arc::new(crate::windows_ocr_engine::WindowsBackend));
+}
+
+pub fn find_backend(name: &str) -> Option<Arc<dyn OcrBackend>> {
+    REGISTRY.read().unwrap().iter().find(|b| b.name() == name).cloned()
+}
+
+/// The primary engine followed by the configured fallbacks, deduplicated.
+/// Unknown names are skipped so a stale config can't break OCR entirely.
+pub fn backend_chain(config: &AppConfig) -> Vec<Arc<dyn OcrBackend>> {
+    let mut chain: Vec<Arc<dyn OcrBackend>> = Vec::new();
+    let names = std::iter::once(&config.ocr_engine).chain(config.ocr_fallback.iter());
+
+    for name in names {
+        if chain.iter().any(|b| b.name() == name) {
+            continue;
+        }
+        match find_backend(name) {
+            Some(backend) => chain.push(backend),
+            None => println!("ocr_backend: Unknown engine '{}', skipping.", name),
+        }
+    }
+    chain
+}
+
+pub fn shutdown_all() {
+    for backend in REGISTRY.read().unwrap().iter() {
+        backend.shutdown();
+    }
+}
+
+#[tauri::command]
+pub fn list_ocr_backends(config_state: tauri::State<'_, crate::config::ConfigState>) -> Result<Vec<BackendInfo>, String> {
+    let config = config_state.config.lock().map_err(|e| e.to_string())?.clone();
+    let registry = REGISTRY.read().map_err(|e| e.to_string())?;
+
+    Ok(registry
+        .iter()
+        .map(|b| BackendInfo {
+            name: b.name().to_string(),
+            capabilities: b.capabilities(),
+            languages: b.supported_languages(&config),
+            health: b.health(&config),
+        })
+        .collect())
+}
@@ -1,317 +1,613 @@
 use screenshots::Screen;
-use image::{DynamicImage, ImageBuffer};
-use windows::Graphics::Imaging::BitmapDecoder;
-use windows::Media::Ocr::OcrEngine;
-use windows::Storage::Streams::{DataWriter, InMemoryRandomAccessStream};
+use image::{DynamicImage, GenericImage};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
 
-static LAST_SCREENSHOT: Lazy<Mutex<Option<DynamicImage>>> = Lazy::new(|| Mutex::new(None));
+use crate::capture_coords::PixelRect;
 
-pub async fn capture_full_screen() -> Result<String, String> {
+/// One display in physical desktop pixels, as reported by the window system.
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+impl MonitorInfo {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width as i32 && y < self.y + self.height as i32
+    }
+}
+
+/// The part of the virtual desktop the cached screenshot covers. With
+/// several monitors this is their bounding box, and `x`/`y` can be negative
+/// (a monitor left of or above the primary one).
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub monitors: Vec<MonitorInfo>,
+}
+
+impl CaptureGeometry {
+    pub fn monitor_at(&self, x: i32, y: i32) -> Option<&MonitorInfo> {
+        monitor_at(&self.monitors, x, y)
+    }
+}
+
+/// The monitor containing a physical desktop point, or the nearest one.
+pub fn monitor_at(monitors: &[MonitorInfo], x: i32, y: i32) -> Option<&MonitorInfo> {
+    monitors.iter().find(|m| m.contains(x, y)).or_else(|| {
+        monitors.iter().min_by_key(|m| {
+            let dx = (m.x - x).max(x - (m.x + m.width as i32)).max(0) as i64;
+            let dy = (m.y - y).max(y - (m.y + m.height as i32)).max(0) as i64;
+            dx * dx + dy * dy
+        })
+    })
+}
+
+struct CachedCapture {
+    /// Changes with every capture, so the overlay never shows a stale image.
+    id: u64,
+    image: DynamicImage,
+    geometry: CaptureGeometry,
+}
+
+static LAST_SCREENSHOT: Lazy<Mutex<Option<CachedCapture>>> = Lazy::new(|| Mutex::new(None));
+static NEXT_CAPTURE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Caches `image` as the current screenshot and returns its id.
+fn cache_capture(image: DynamicImage, geometry: CaptureGeometry) -> u64 {
+    let id = NEXT_CAPTURE_ID.fetch_add(1, Ordering::Relaxed);
+    *LAST_SCREENSHOT.lock().unwrap() = Some(CachedCapture { id, image, geometry });
+    id
+}
+
+/// Geometry of the cached screenshot, for sizing the capture overlay and
+/// mapping selections back to desktop pixels.
+pub fn capture_geometry() -> Option<CaptureGeometry> {
+    LAST_SCREENSHOT.lock().unwrap().as_ref().map(|c| c.geometry.clone())
+}
+
+/// Replaces the cached screenshot, e.g. with a stitched scrolling capture.
+/// Selections and crops then refer to `image`, laid out by `geometry`.
+pub fn set_cached_capture(image: DynamicImage, geometry: CaptureGeometry) {
+    println!("ocr_core: cached capture replaced: {}x{} at ({}, {})", image.width(), image.height(), geometry.x, geometry.y);
+    cache_capture(image, geometry);
+}
+
+/// The cached screenshot `id` encoded as BMP, straight from memory, for the
+/// overlay's `booboo://capture/<id>` image. `None` once a newer capture has
+/// replaced it.
+pub fn cached_capture_bmp(id: u64) -> Option<Vec<u8>> {
+    let image = {
+        let cache = LAST_SCREENSHOT.lock().unwrap();
+        let cached = cache.as_ref().filter(|c| c.id == id)?;
+        cached.image.clone()
+    };
+
+    // BMP encoding is nearly zero-cost (no compression)
+    let start = std::time::Instant::now();
+    let mut bytes = std::io::Cursor::new(Vec::with_capacity(image.as_bytes().len() + 256));
+    if let Err(e) = image.write_to(&mut bytes, image::ImageFormat::Bmp) {
+        println!("ocr_core: BMP encoding failed: {}", e);
+        return None;
+    }
+    println!("ocr_core: BMP encoding took {:?}", start.elapsed());
+    Some(bytes.into_inner())
+}
+
+/// Captures the given monitors, stitches them into one image laid out like
+/// the virtual desktop, caches it and returns its id for
+/// `cached_capture_bmp`.
+pub async fn capture_full_screen(monitors: &[MonitorInfo]) -> Result<u64, String> {
     let start_time = std::time::Instant::now();
     let screens = Screen::all().map_err(|e| e.to_string())?;
-    let screen = screens.into_iter().next().ok_or("No screen found")?;
-    
     println!("ocr_core: Screen::all took {:?}", start_time.elapsed());
+    if monitors.is_empty() {
+        return Err("No screen found".to_string());
+    }
+
+    let x0 = monitors.iter().map(|m| m.x).min().unwrap_or(0);
+    let y0 = monitors.iter().map(|m| m.y).min().unwrap_or(0);
+    let x1 = monitors.iter().map(|m| m.x + m.width as i32).max().unwrap_or(0);
+    let y1 = monitors.iter().map(|m| m.y + m.height as i32).max().unwrap_or(0);
+    let geometry = CaptureGeometry { x: x0, y: y0, width: (x1 - x0) as u32, height: (y1 - y0) as u32, monitors: monitors.to_vec() };
+
     let capture_start = std::time::Instant::now();
-    let image = screen.capture().map_err(|e| e.to_string())?;
-    println!("ocr_core: screen.capture() took {:?}", capture_start.elapsed());
-    
-    let dynamic_image = DynamicImage::ImageRgba8(
-        ImageBuffer::from_raw(image.width(), image.height(), image.into_raw())
-            .ok_or("Failed to create image buffer")?
-    );
-
-    let cache_start = std::time::Instant::now();
-    // Cache the FULL resolution image for subsequent accurate cropping
-    {
-        let mut cache = LAST_SCREENSHOT.lock().unwrap();
-        *cache = Some(dynamic_image.clone());
-    }
-    println!("ocr_core: caching took {:?}", cache_start.elapsed());
-
-    // Convert to BMP file for zero-latency transfer
-    let start_encode = std::time::Instant::now();
-    let temp_dir = std::env::temp_dir();
-    let file_path = temp_dir.join("booboo_capture.bmp");
-    
-    // BMP encoding is nearly zero-cost (no compression). 
-    // Disk write for ~15-30MB on SSD is much faster than JPEG software encoding.
-    dynamic_image.save_with_format(&file_path, image::ImageFormat::Bmp)
-        .map_err(|e| e.to_string())?;
+    let dynamic_image = if let [monitor] = monitors {
+        // Single monitor: no canvas, no copy
+        DynamicImage::ImageRgba8(capture_monitor(&screens, monitor)?)
+    } else {
+        let mut canvas = image::RgbaImage::new(geometry.width, geometry.height);
+        for monitor in monitors {
+            let image = capture_monitor(&screens, monitor)?;
+            canvas
+                .copy_from(&image, (monitor.x - x0) as u32, (monitor.y - y0) as u32)
+                .map_err(|e| e.to_string())?;
+        }
+        DynamicImage::ImageRgba8(canvas)
+    };
+    println!("ocr_core: captured {} monitor(s) in {:?}, desktop area {:?}", monitors.len(), capture_start.elapsed(), (x0, y0, geometry.width, geometry.height));
 
-    println!("ocr_core: BMP save took {:?}", start_encode.elapsed());
+    // Cache the FULL resolution image for subsequent accurate cropping; the
+    // overlay loads it from memory through the custom protocol
+    let id = cache_capture(dynamic_image, geometry);
     println!("ocr_core: Total capture_full_screen backend took {:?}", start_time.elapsed());
-    
-    Ok(file_path.to_string_lossy().to_string())
+    Ok(id)
 }
 
-pub async fn capture_area(x: i32, y: i32, width: u32, height: u32) -> Result<DynamicImage, String> {
-    println!("ocr_core: capture_area (crop) start: x={}, y={}, w={}, h={}", x, y, width, height);
+/// Captures one monitor at its physical resolution. `screenshots` reports
+/// positions in its own units (logical on macOS, physical on a DPI-aware
+/// Windows process), so screens are matched by origin times their own scale.
+fn capture_monitor(screens: &[Screen], monitor: &MonitorInfo) -> Result<image::RgbaImage, String> {
+    let screen = screens
+        .iter()
+        .find(|s| {
+            let info = &s.display_info;
+            let sx = (info.x as f32 * info.scale_factor).round() as i32;
+            let sy = (info.y as f32 * info.scale_factor).round() as i32;
+            ((sx - monitor.x).abs() <= 2 && (sy - monitor.y).abs() <= 2) || (info.x == monitor.x && info.y == monitor.y)
+        })
+        .or_else(|| screens.first())
+        .ok_or("No screen found")?;
+
+    let image = screen.capture().map_err(|e| e.to_string())?;
+    let image = image::RgbaImage::from_raw(image.width(), image.height(), image.into_raw())
+        .ok_or("Failed to create image buffer")?;
+    if image.dimensions() == (monitor.width, monitor.height) {
+        Ok(image)
+    } else {
+        println!("ocr_core: '{}' captured at {:?}, expected {}x{}; resizing", monitor.name, image.dimensions(), monitor.width, monitor.height);
+        Ok(image::imageops::resize(&image, monitor.width, monitor.height, image::imageops::FilterType::Triangle))
+    }
+}
+
+/// Captures a rectangle of `monitor`, in physical desktop pixels, straight
+/// from the screen. The cached screenshot is left alone.
+pub fn capture_region(monitor: &MonitorInfo, x: i32, y: i32, width: u32, height: u32) -> Result<image::RgbaImage, String> {
+    let screens = Screen::all().map_err(|e| e.to_string())?;
+    let image = capture_monitor(&screens, monitor)?;
+    let left = (x - monitor.x).clamp(0, monitor.width as i32 - 1) as u32;
+    let top = (y - monitor.y).clamp(0, monitor.height as i32 - 1) as u32;
+    let width = width.clamp(1, monitor.width - left);
+    let height = height.clamp(1, monitor.height - top);
+    Ok(image::imageops::crop_imm(&image, left, top, width, height).to_image())
+}
+
+/// Crops a rectangle, given in pixels of the cached screenshot (see
+/// `capture_coords::map_selection`), from the cached screenshot.
+pub async fn capture_area(rect: PixelRect) -> Result<DynamicImage, String> {
+    println!("ocr_core: capture_area (crop) start: {:?}", rect);
     
     let cached_image = {
         let cache = LAST_SCREENSHOT.lock().unwrap();
-        cache.clone().ok_or("No cached screenshot found. Please capture full screen first.")?
+        let cached = cache.as_ref().ok_or("No cached screenshot found. Please capture full screen first.")?;
+        cached.image.clone()
     };
 
-    // Coordinates are absolute physical from lib.rs
-    let px = x.max(0) as u32;
-    let py = y.max(0) as u32;
-    let pw = width;
-    let ph = height;
-    
-    // Safety check: ensure crop area is within bounds
-    let crop_x = px.min(cached_image.width() - 1);
-    let crop_y = py.min(cached_image.height() - 1);
-    let crop_w = pw.min(cached_image.width() - crop_x);
-    let crop_h = ph.min(cached_image.height() - crop_y);
+    // `map_selection` already clipped to the capture; this only guards
+    // against a screenshot replaced in between
+    let left = rect.x.min(cached_image.width().saturating_sub(1));
+    let top = rect.y.min(cached_image.height().saturating_sub(1));
+    let right = (rect.x + rect.width).clamp(left + 1, cached_image.width().max(1));
+    let bottom = (rect.y + rect.height).clamp(top + 1, cached_image.height().max(1));
 
-    println!("ocr_core: cropping cached image: x={}, y={}, w={}, h={}", crop_x, crop_y, crop_w, crop_h);
+    println!("ocr_core: cropping cached image: x={}, y={}, w={}, h={}", left, top, right - left, bottom - top);
     
-    let cropped = cached_image.crop_imm(crop_x, crop_y, crop_w, crop_h);
+    let cropped = cached_image.crop_imm(left, top, right - left, bottom - top);
     
     println!("ocr_core: crop done. size: {}x{}", cropped.width(), cropped.height());
     Ok(cropped)
 }
 
-pub async fn run_ocr(image: DynamicImage, config: &crate::config::AppConfig) -> Result<String, String> {
-    println!("ocr_core: run_ocr start. Input size: {}x{}, Engine: {}", image.width(), image.height(), config.ocr_engine);
-    
-    match config.ocr_engine.as_str() {
-        "paddle" => {
-            // 1. Save temp image for PaddleOCR (it needs a file path)
-            let temp_dir = std::env::temp_dir();
-            let temp_file = temp_dir.join("ocr_paddle_input.png");
-            let file_path_str = temp_file.to_string_lossy().to_string();
-            
-            image.save_with_format(&temp_file, image::ImageFormat::Png).map_err(|e| e.to_string())?;
-
-            // 2. Try PaddleOCR
-            println!("ocr_core: Attempting PaddleOCR...");
-            match crate::paddle_ocr_engine::run_paddle_ocr(&file_path_str) {
-                Ok(text) => {
-                    println!("ocr_core: PaddleOCR success. Length: {}", text.len());
-                    Ok(text)
-                },
-                Err(e) => {
-                    println!("ocr_core: PaddleOCR failed: {}. Falling back to Windows OCR.", e);
-                    // Fallback to Windows OCR if Paddle fails (transient error or process issue)
-                    run_windows_ocr_logic(image).await
-                }
-            }
-        },
-        "windows" => {
-            run_windows_ocr_logic(image).await
-        },
-        _ => {
-            // Default or unknown -> Windows fallback
-            println!("ocr_core: Unknown engine '{}', defaulting to Windows OCR.", config.ocr_engine);
-            run_windows_ocr_logic(image).await
+/// What a capture is expected to contain; chosen per capture by the caller.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OcrMode {
+    /// Prose: text is normalized and wrapped lines are merged into paragraphs.
+    Text,
+    /// Source code: indentation is rebuilt from the line boxes, CJK spacing is
+    /// left alone and commonly confused symbols are corrected.
+    Code,
+    /// QR codes and barcodes only; each payload becomes one line.
+    Decode,
+}
+
+impl OcrMode {
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name {
+            Some("code") => OcrMode::Code,
+            Some("decode") => OcrMode::Decode,
+            _ => OcrMode::Text,
         }
     }
 }
 
-async fn run_windows_ocr_logic(image: DynamicImage) -> Result<String, String> {
-    println!("ocr_core: Running Windows OCR logic...");
+/// Blocking: every engine call runs on the calling thread, so callers on the
+/// async runtime go through `spawn_blocking`.
+pub fn run_ocr(image: DynamicImage, config: &crate::config::AppConfig, mode: OcrMode) -> Result<crate::ocr_document::OcrDocument, String> {
+    println!("ocr_core: run_ocr start. Input size: {}x{}, Engine: {}, Mode: {:?}", image.width(), image.height(), config.ocr_engine, mode);
 
-    // 1. Scale up by 2.5x using Lanczos3
-    // Triangle at 3x was too blurry for dense text.
-    // Lanczos3 provides the best trade-off between sharpness (for glitch font) and artifact control (for punctuation).
-    let new_w = (image.width() as f64 * 2.5) as u32;
-    let new_h = (image.height() as f64 * 2.5) as u32;
-    let upscaled = image.resize(new_w, new_h, image::imageops::FilterType::Lanczos3);
-    
-    // 2. Convert to grayscale
-    let luma = upscaled.to_luma8();
-    
-    // 3. Prepare Inverted Copy (for dark mode support)
-    let mut luma_inverted = luma.clone();
-    for p in luma_inverted.pixels_mut() {
-        p.0 = [255 - p.0[0]];
+    if mode == OcrMode::Decode {
+        let mut doc = crate::ocr_document::OcrDocument::new("barcode", image.width(), image.height());
+        doc.codes = crate::barcode::decode(&image);
+        doc.lines = doc
+            .codes
+            .iter()
+            .map(|code| crate::ocr_document::OcrLine { text: code.text.clone(), words: Vec::new(), polygon: code.polygon.clone(), confidence: None })
+            .collect();
+        return Ok(doc);
     }
-    
-    // 4. Run OCR on BOTH strategies PARALLEL
-    // Save debug images for troubleshooting (Commented out for production)
-    // let _ = luma.save("C:/Users/Administrator/Desktop/ocr_debug_normal.png");
-    // let _ = luma_inverted.save("C:/Users/Administrator/Desktop/ocr_debug_inverted.png");
-
-    let mut png_normal = Vec::new();
-    DynamicImage::ImageLuma8(luma).write_to(&mut std::io::Cursor::new(&mut png_normal), image::ImageFormat::Png).map_err(|e| e.to_string())?;
-
-    let mut png_inverted = Vec::new();
-    DynamicImage::ImageLuma8(luma_inverted).write_to(&mut std::io::Cursor::new(&mut png_inverted), image::ImageFormat::Png).map_err(|e| e.to_string())?;
-
-    // Run in parallel for speed
-    let task_normal = tauri::async_runtime::spawn_blocking(move || {
-        use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
-        unsafe { let _ = CoInitializeEx(None, COINIT_MULTITHREADED); }
-        tauri::async_runtime::block_on(async {
-            run_windows_native_ocr_v2(png_normal).await
-        })
-    });
 
-    let task_inverted = tauri::async_runtime::spawn_blocking(move || {
-        use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
-        unsafe { let _ = CoInitializeEx(None, COINIT_MULTITHREADED); }
-        tauri::async_runtime::block_on(async {
-            run_windows_native_ocr_v2(png_inverted).await
-        })
-    });
+    // Engines read dark text on a light background best. "dual" keeps the old
+    // behaviour of recognizing both polarities and keeping the better result.
+    let candidates = match config.ocr_polarity.as_str() {
+        "off" => vec![image.clone()],
+        "dual" => {
+            let mut inverted = image.clone();
+            inverted.invert();
+            vec![image.clone(), inverted]
+        }
+        _ => {
+            let (normalized, polarity) = normalize_polarity(&image);
+            println!("ocr_core: Polarity {:?}", polarity);
+            vec![normalized]
+        }
+    };
 
-    // Wait for both (sequential await on handles is fine loop-free)
-    let res_normal_r = task_normal.await;
-    let res_inverted_r = task_inverted.await;
+    // Measured once on the normalized capture and applied to every candidate
+    let orientations = if config.ocr_deskew {
+        crate::image_orientation::detect(&candidates[0])
+    } else {
+        vec![crate::image_orientation::Orientation::Upright]
+    };
 
-    // Handle join errors and inner errors
-    let res_normal = res_normal_r.map_err(|e| e.to_string())??;
-    let res_inverted = res_inverted_r.map_err(|e| e.to_string())??;
+    let chain = crate::ocr_backend::backend_chain(config);
+    let mut last_error = format!("No usable OCR engine configured ('{}')", config.ocr_engine);
 
-    println!("ocr_core: Result Normal len: {}, Inverted len: {}", res_normal.len(), res_inverted.len());
+    'backends: for backend in chain {
+        if let crate::ocr_backend::BackendHealth::Unavailable(reason) = backend.health(config) {
+            println!("ocr_core: Skipping '{}': {}", backend.name(), reason);
+            last_error = format!("{}: {}", backend.name(), reason);
+            continue;
+        }
 
-    // heuristic: pick the longer one
-    if res_inverted.len() > res_normal.len() {
-        println!("ocr_core: Selected INVERTED result.");
-        Ok(res_inverted)
+        println!("ocr_core: Attempting {}...", backend.name());
+        let mut results = Vec::new();
+        for candidate in &candidates {
+            for orientation in &orientations {
+                match recognize_prepared(backend.as_ref(), candidate, orientation, config, mode) {
+                    Ok(doc) => results.push(doc),
+                    Err(e) => {
+                        // Transient error or process issue: the other candidates
+                        // would fail the same way, so move on to the next engine
+                        println!("ocr_core: {} failed: {}. Trying next engine.", backend.name(), e);
+                        last_error = format!("{}: {}", backend.name(), e);
+                        continue 'backends;
+                    }
+                }
+            }
+        }
+
+        if let Some((best, score)) = crate::ocr_scoring::pick_best(&results) {
+            let mut doc = results.swap_remove(best);
+            println!("ocr_core: {} success. Lines: {}", backend.name(), doc.lines.len());
+            doc.score = Some(score);
+            if mode == OcrMode::Code {
+                format_code(&mut doc);
+            } else if config.ocr_merge_paragraphs {
+                doc.paragraphs = crate::ocr_layout::paragraphs(&doc);
+            }
+            if config.ocr_decode_barcodes {
+                doc.codes = crate::barcode::decode(&image);
+            }
+            return Ok(doc);
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Orients the capture, runs the engine's preprocessing pipeline, cleans up
+/// the text for `mode` and maps the result back onto `image`'s coordinates.
+fn recognize_prepared(
+    backend: &dyn crate::ocr_backend::OcrBackend,
+    image: &DynamicImage,
+    orientation: &crate::image_orientation::Orientation,
+    config: &crate::config::AppConfig,
+    mode: OcrMode,
+) -> Result<crate::ocr_document::OcrDocument, String> {
+    let oriented = orientation.apply(image);
+    let prepared = crate::image_preprocess::apply(&oriented, config.preprocess_steps(backend.name()));
+    let mut doc = backend.recognize(&prepared.image, config)?;
+    if mode == OcrMode::Code {
+        for line in &mut doc.lines {
+            line.text = fix_code_symbols(&line.text);
+            for word in &mut line.words {
+                word.text = fix_code_symbols(&word.text);
+            }
+        }
     } else {
-        println!("ocr_core: Selected NORMAL result.");
-        Ok(res_normal)
+        crate::text_normalize::normalize_document(&mut doc, &crate::text_normalize::NormalizeOptions::from_config(config));
     }
+    // Report boxes in the coordinates of the caller's image, not the preprocessed one
+    doc.transform(1.0 / prepared.scale, 0.0, 0.0);
+    orientation.restore(&mut doc, image.width(), image.height());
+    Ok(doc)
 }
 
-fn clean_ocr_text(text: &str) -> String {
-    let mut cleaned = String::new();
-    let chars: Vec<char> = text.chars().collect();
-    
-    for i in 0..chars.len() {
-        let c = chars[i];
-        if c == ' ' {
-            // Check if surrounded by CJK characters
-            if i > 0 && i < chars.len() - 1 {
-                let prev = chars[i-1];
-                let next = chars[i+1];
-                if is_cjk(prev) && is_cjk(next) {
-                    // Skip space between CJK
-                    continue;
-                }
+/// Rebuilds a code listing from the line boxes: lines sharing a baseline are
+/// rejoined, each row is indented by its x-offset in character widths
+/// (snapped to the indent unit the listing appears to use), and large
+/// vertical gaps become blank lines. The result replaces `doc.lines` and is
+/// also stored as a single `Code` paragraph so `reading_text` keeps it intact.
+fn format_code(doc: &mut crate::ocr_document::OcrDocument) {
+    use crate::ocr_document::{polygon_bounds, rect_polygon, OcrLine, OcrParagraph, ParagraphKind};
+    type Bounds = (f32, f32, f32, f32);
+
+    let boxed: Vec<(OcrLine, Bounds)> = std::mem::take(&mut doc.lines)
+        .into_iter()
+        .filter(|l| !l.text.trim().is_empty())
+        .filter_map(|l| {
+            let bounds = polygon_bounds(&l.polygon)?;
+            Some((l, bounds))
+        })
+        .collect();
+    if boxed.is_empty() {
+        return;
+    }
+
+    // Monospace: every glyph has the same advance, so box width / length
+    // gives the character width. The median ignores boxes with odd padding.
+    let mut advances: Vec<f32> = boxed
+        .iter()
+        .filter(|(l, _)| l.text.trim().chars().count() >= 4)
+        .map(|(l, b)| (b.2 - b.0) / l.text.trim().chars().count() as f32)
+        .collect();
+    if advances.is_empty() {
+        advances = boxed.iter().map(|(l, b)| (b.2 - b.0) / l.text.trim().chars().count().max(1) as f32).collect();
+    }
+    advances.sort_by(f32::total_cmp);
+    let char_width = advances[advances.len() / 2].max(1.0);
+
+    // Rows: segments of one source line that the engine split at wide gaps,
+    // grouped the same way as paragraphs
+    let groups = crate::ocr_layout::group_rows(boxed.iter().enumerate().map(|(i, (_, b))| (i, *b)));
+    let mut slots: Vec<Option<OcrLine>> = boxed.into_iter().map(|(l, _)| Some(l)).collect();
+    let rows: Vec<(Vec<OcrLine>, Bounds)> = groups
+        .into_iter()
+        .map(|g| (g.members.iter().filter_map(|&i| slots[i].take()).collect(), g.bounds))
+        .collect();
+
+    let left = rows.iter().map(|(_, r)| r.0).fold(f32::MAX, f32::min);
+    let columns: Vec<f32> = rows.iter().map(|(_, r)| (r.0 - left) / char_width).collect();
+    let unit = indent_unit(&columns);
+
+    // Line pitch; rows further apart than that have blank lines between them
+    let mut pitches: Vec<f32> = rows.windows(2).map(|w| w[1].1 .1 - w[0].1 .1).collect();
+    pitches.sort_by(f32::total_cmp);
+    let pitch = pitches.get(pitches.len() / 2).copied().unwrap_or(1.0).max(1.0);
+
+    let mut lines = Vec::with_capacity(rows.len());
+    let mut code = String::new();
+    let mut prev_top: Option<f32> = None;
+    for ((segments, r), cols) in rows.into_iter().zip(columns) {
+        let indent = match unit {
+            Some(unit) => (cols / unit as f32).round() as usize * unit,
+            None => cols.round() as usize,
+        };
+        let mut text = " ".repeat(indent);
+        let mut last_right: Option<f32> = None;
+        for segment in &segments {
+            let bounds = polygon_bounds(&segment.polygon).unwrap_or_default();
+            if let Some(right) = last_right {
+                let gap = ((bounds.0 - right) / char_width).round().max(1.0) as usize;
+                text.push_str(&" ".repeat(gap));
             }
+            text.push_str(segment.text.trim());
+            last_right = Some(bounds.2);
         }
-        cleaned.push(c);
+
+        if let Some(top) = prev_top {
+            let blank = ((r.1 - top) / pitch - 1.0).round().clamp(0.0, 3.0) as usize;
+            code.push_str(&"\n".repeat(blank + 1));
+        }
+        code.push_str(&text);
+        prev_top = Some(r.1);
+
+        let scores: Vec<f32> = segments.iter().filter_map(|s| s.confidence).collect();
+        lines.push(OcrLine {
+            text,
+            words: segments.into_iter().flat_map(|s| s.words).collect(),
+            polygon: rect_polygon(r.0, r.1, r.2 - r.0, r.3 - r.1),
+            confidence: (!scores.is_empty()).then(|| scores.iter().sum::<f32>() / scores.len() as f32),
+        });
     }
-    cleaned
+
+    let (x0, y0, x1, y1) = lines
+        .iter()
+        .filter_map(|l| polygon_bounds(&l.polygon))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+        .unwrap_or_default();
+    doc.paragraphs = vec![OcrParagraph {
+        text: code,
+        kind: ParagraphKind::Code,
+        lines: (0..lines.len()).collect(),
+        polygon: rect_polygon(x0, y0, x1 - x0, y1 - y0),
+    }];
+    doc.lines = lines;
 }
 
-fn is_cjk(c: char) -> bool {
-    // Basic CJK Unified Ideographs block
-    (c >= '\u{4E00}' && c <= '\u{9FFF}') ||
-    // CJK Symbols and Punctuation
-    (c >= '\u{3000}' && c <= '\u{303F}') ||
-    // Fullwidth forms
-    (c >= '\u{FF00}' && c <= '\u{FFEF}')
+/// The largest common indent step (8, 4, 3 or 2 columns) that nearly every
+/// row's offset is close to a multiple of, or `None` to keep raw columns.
+fn indent_unit(columns: &[f32]) -> Option<usize> {
+    [8usize, 4, 3, 2].into_iter().find(|&unit| {
+        let u = unit as f32;
+        let fits = columns.iter().filter(|&&c| (c - (c / u).round() * u).abs() <= 0.6).count();
+        let indented = columns.iter().any(|&c| c >= u - 0.6);
+        indented && fits as f32 >= columns.len() as f32 * 0.9
+    })
 }
 
-async fn run_windows_native_ocr_v2(png_bytes: Vec<u8>) -> Result<String, String> {
-    let stream = InMemoryRandomAccessStream::new().map_err(|e: windows::core::Error| e.to_string())?;
-    let writer = stream.GetOutputStreamAt(0).map_err(|e: windows::core::Error| e.to_string())?;
-    let data_writer = DataWriter::CreateDataWriter(&writer).map_err(|e: windows::core::Error| e.to_string())?;
-    data_writer.WriteBytes(&png_bytes).map_err(|e: windows::core::Error| e.to_string())?;
-    data_writer.StoreAsync().map_err(|e: windows::core::Error| e.to_string())?.await.map_err(|e: windows::core::Error| e.to_string())?;
-    data_writer.FlushAsync().map_err(|e: windows::core::Error| e.to_string())?.await.map_err(|e: windows::core::Error| e.to_string())?;
-
-    let decoder = BitmapDecoder::CreateAsync(&stream).map_err(|e: windows::core::Error| e.to_string())?.await.map_err(|e: windows::core::Error| e.to_string())?;
-    let bitmap = decoder.GetSoftwareBitmapAsync().map_err(|e: windows::core::Error| e.to_string())?.await.map_err(|e: windows::core::Error| e.to_string())?;
-
-    let mut results = Vec::new();
-
-    // Strategy: Try User Profile first
-    if let Ok(engine) = OcrEngine::TryCreateFromUserProfileLanguages() {
-        if let Ok(result) = engine.RecognizeAsync(&bitmap).map_err(|e| e.to_string())?.await {
-            // Use Lines() instead of Text() to ensure we get explicit line breaks
-            if let Ok(lines) = result.Lines() {
-                let mut full_text = String::new();
-                for line in lines {
-                    if let Ok(line_text) = line.Text() {
-                         let raw_line = line_text.to_string();
-                         let cleaned = clean_ocr_text(&raw_line);
-                         if !cleaned.trim().is_empty() {
-                             if !full_text.is_empty() {
-                                 full_text.push('\n');
-                             }
-                             full_text.push_str(&cleaned);
-                         }
-                    }
-                }
-                println!("ocr_core: UserProfile Text Len: {}", full_text.len());
-                if !full_text.is_empty() {
-                    results.push((full_text.len(), full_text));
-                }
+/// Symbol corrections for monospace code fonts: typographic quotes and
+/// full-width forms back to ASCII, `O`/`l`/`I` inside numbers to digits and
+/// `1`/`0` inside identifiers to letters.
+pub fn fix_code_symbols(text: &str) -> String {
+    let mut mapped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '“' | '”' | '„' | '‟' | '″' => mapped.push('"'),
+            '‘' | '’' | '‚' | '‛' | '′' | '´' => mapped.push('\''),
+            '–' | '—' | '−' => mapped.push('-'),
+            '…' => mapped.push_str("..."),
+            '\u{3000}' => mapped.push(' '),
+            '\u{FF01}'..='\u{FF5E}' => mapped.push(char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)),
+            _ => mapped.push(c),
+        }
+    }
+
+    let chars: Vec<char> = mapped.chars().collect();
+    let mut out = String::with_capacity(mapped.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if !(chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+            i += 1;
+        }
+        let token = &chars[start..i];
+
+        if token[0].is_ascii_digit() {
+            // Numbers start with a digit; "1O0" and "2l" are misread digits.
+            // Lowercase "o" is left alone for octal literals like 0o17.
+            if token.iter().all(|c| c.is_ascii_digit() || matches!(c, 'O' | 'l' | 'I')) {
+                out.extend(token.iter().map(|c| match c {
+                    'O' => '0',
+                    'l' | 'I' => '1',
+                    c => *c,
+                }));
+            } else {
+                out.extend(token);
             }
+            continue;
+        }
+
+        // Identifiers: "fi1e" -> "file", "MAX_C0UNT" -> "MAX_COUNT"
+        for (k, &c) in token.iter().enumerate() {
+            let prev = k.checked_sub(1).map(|p| token[p]);
+            let next = token.get(k + 1).copied();
+            let fixed = match c {
+                // Two letters before, so version tags like "v1beta" survive
+                '1' if k >= 2 && token[k - 2..k].iter().all(|p| p.is_ascii_lowercase()) && next.is_some_and(|n| n.is_ascii_lowercase()) => 'l',
+                '0' if prev.is_some_and(|p| p.is_ascii_uppercase()) && next.is_some_and(|n| n.is_ascii_uppercase()) => 'O',
+                c => c,
+            };
+            out.push(fixed);
         }
     }
+    out
+}
 
-    // Attempt: Chinese specific
-    if let Ok(lang) = windows::Globalization::Language::CreateLanguage(&windows::core::HSTRING::from("zh-Hans")) {
-        if let Ok(engine) = OcrEngine::TryCreateFromLanguage(&lang) {
-            if let Ok(result) = engine.RecognizeAsync(&bitmap).map_err(|e| e.to_string())?.await {
-                if let Ok(lines) = result.Lines() {
-                    let mut full_text = String::new();
-                    for line in lines {
-                        if let Ok(line_text) = line.Text() {
-                             let raw_line = line_text.to_string();
-                             let cleaned = clean_ocr_text(&raw_line);
-                             if !cleaned.trim().is_empty() {
-                                 if !full_text.is_empty() {
-                                     full_text.push('\n');
-                                 }
-                                 full_text.push_str(&cleaned);
-                             }
-                        }
-                    }
-                    println!("ocr_core: zh-Hans Text Len: {}", full_text.len());
-                    if !full_text.is_empty() {
-                        results.push((full_text.len(), full_text));
-                    }
-                }
+/// Background polarity of a capture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Polarity {
+    /// Dark text on a light background; passed through untouched.
+    Light,
+    /// Light text on a dark background; the whole image is inverted.
+    Dark,
+    /// Both kinds of region (e.g. a dark sidebar next to a light editor);
+    /// only the dark tiles are inverted. Holds the share of dark tiles.
+    Mixed(f32),
+}
+
+/// Luminance below which a region's background counts as dark.
+const DARK_BACKGROUND_LUMA: u8 = 110;
+/// Edge length of the tiles used to find mixed regions.
+const POLARITY_TILE: u32 = 96;
+
+/// Decides whether the capture is light-on-dark and inverts only what needs
+/// it. The background is the most common brightness: the border ring (which
+/// rarely cuts through text) and the overall median have to agree before the
+/// whole image flips, and per-tile medians catch mixed layouts.
+pub fn normalize_polarity(image: &DynamicImage) -> (DynamicImage, Polarity) {
+    let gray = image.to_luma8();
+    let (w, h) = gray.dimensions();
+    if w == 0 || h == 0 {
+        return (image.clone(), Polarity::Light);
+    }
+
+    let ring = (w.min(h) / 20).clamp(1, 4);
+    let mut border = Vec::new();
+    for (x, y, p) in gray.enumerate_pixels() {
+        if x < ring || y < ring || x >= w - ring || y >= h - ring {
+            border.push(p.0[0]);
+        }
+    }
+    let border_dark = median(&mut border) < DARK_BACKGROUND_LUMA;
+    let global_dark = median(&mut gray.as_raw().clone()) < DARK_BACKGROUND_LUMA;
+
+    // Per-tile background
+    let cols = w.div_ceil(POLARITY_TILE);
+    let rows = h.div_ceil(POLARITY_TILE);
+    let mut dark_tiles = Vec::new();
+    for row in 0..rows {
+        for col in 0..cols {
+            let (x0, y0) = (col * POLARITY_TILE, row * POLARITY_TILE);
+            let (tw, th) = (POLARITY_TILE.min(w - x0), POLARITY_TILE.min(h - y0));
+            let mut values: Vec<u8> = (y0..y0 + th)
+                .flat_map(|y| (x0..x0 + tw).map(move |x| (x, y)))
+                .map(|(x, y)| gray.get_pixel(x, y).0[0])
+                .collect();
+            if median(&mut values) < DARK_BACKGROUND_LUMA {
+                dark_tiles.push((x0, y0, tw, th));
             }
         }
     }
+    let dark_share = dark_tiles.len() as f32 / (cols * rows) as f32;
 
-    // Attempt: English specific
-    if let Ok(lang) = windows::Globalization::Language::CreateLanguage(&windows::core::HSTRING::from("en-US")) {
-        if let Ok(engine) = OcrEngine::TryCreateFromLanguage(&lang) {
-             if let Ok(result) = engine.RecognizeAsync(&bitmap).map_err(|e| e.to_string())?.await {
-                if let Ok(lines) = result.Lines() {
-                    let mut full_text = String::new();
-                    for line in lines {
-                        if let Ok(line_text) = line.Text() {
-                             let s = line_text.to_string();
-                             if !s.trim().is_empty() {
-                                 if !full_text.is_empty() {
-                                     full_text.push('\n');
-                                 }
-                                 full_text.push_str(&s);
-                             }
-                        }
-                    }
-                    println!("ocr_core: en-US Text Len: {}", full_text.len());
-                    if !full_text.is_empty() {
-                        results.push((full_text.len(), full_text));
-                    }
+    // A few odd tiles (an icon, a photo) are not worth a mixed pass
+    if dark_tiles.is_empty() || (dark_share < 0.15 && !border_dark) {
+        return (image.clone(), Polarity::Light);
+    }
+    // Large glyphs can fool a tile median, so a mostly-dark capture only flips
+    // as a whole when the border and the overall histogram agree
+    if dark_share > 0.85 || (dark_share > 0.6 && border_dark && global_dark) {
+        let mut inverted = image.clone();
+        inverted.invert();
+        return (inverted, Polarity::Dark);
+    }
+
+    let mut mixed = image.to_rgba8();
+    for (x0, y0, tw, th) in dark_tiles {
+        for y in y0..y0 + th {
+            for x in x0..x0 + tw {
+                let p = mixed.get_pixel_mut(x, y);
+                for c in &mut p.0[..3] {
+                    *c = 255 - *c;
                 }
             }
         }
     }
+    (DynamicImage::ImageRgba8(mixed), Polarity::Mixed(dark_share))
+}
 
-    // Pick the one with the longest text
-    results.sort_by(|a, b| b.0.cmp(&a.0));
-    
-    if let Some((_, text)) = results.into_iter().next() {
-        println!("ocr_core: best result selected. length: {}", text.len());
-        Ok(text)
-    } else {
-        println!("ocr_core: no recognition results from any engine");
-        Ok(String::new())
+fn median(values: &mut [u8]) -> u8 {
+    if values.is_empty() {
+        return 255;
     }
+    let mid = values.len() / 2;
+    *values.select_nth_unstable(mid).1
 }
 
 pub fn image_to_base64(image: &DynamicImage) -> String {
@@ -327,3 +623,169 @@ pub fn image_to_base64(image: &DynamicImage) -> String {
     let vec = buffer.into_inner();
     general_purpose::STANDARD.encode(&vec)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr_document::{rect_polygon, OcrDocument, OcrLine, ParagraphKind};
+    use image::{GrayImage, Luma};
+
+    /// A code capture from `(text, x, y)` line boxes in a 10 px wide
+    /// monospace font.
+    fn code_document(lines: &[(&str, f32, f32)]) -> OcrDocument {
+        let mut doc = OcrDocument::new("test", 400, 200);
+        doc.lines = lines
+            .iter()
+            .map(|&(text, x, y)| OcrLine {
+                text: text.to_string(),
+                words: Vec::new(),
+                polygon: rect_polygon(x, y, text.chars().count() as f32 * 10.0, 16.0),
+                confidence: None,
+            })
+            .collect();
+        doc
+    }
+
+    #[test]
+    fn code_symbols_are_fixed_inside_numbers_and_identifiers() {
+        assert_eq!(fix_code_symbols("let size = 1O0 + 2l;"), "let size = 100 + 21;");
+        assert_eq!(fix_code_symbols("open(fi1e, MAX_C0UNT)"), "open(file, MAX_COUNT)");
+        assert_eq!(fix_code_symbols("print(“done”)…"), "print(\"done\")...");
+        assert_eq!(fix_code_symbols("ｘ　＝　ｙ；"), "x = y;");
+    }
+
+    /// Reads the same confusable-laden line whatever it is shown.
+    struct FixedText;
+
+    impl crate::ocr_backend::OcrBackend for FixedText {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+        fn capabilities(&self) -> crate::ocr_backend::OcrCapabilities {
+            Default::default()
+        }
+        fn supported_languages(&self, _config: &crate::config::AppConfig) -> Vec<String> {
+            Vec::new()
+        }
+        fn health(&self, _config: &crate::config::AppConfig) -> crate::ocr_backend::BackendHealth {
+            crate::ocr_backend::BackendHealth::Ready
+        }
+        fn recognize(&self, image: &DynamicImage, _config: &crate::config::AppConfig) -> Result<OcrDocument, String> {
+            let mut doc = code_document(&[("fi1e = 1O0;", 10.0, 10.0)]);
+            (doc.width, doc.height) = (image.width(), image.height());
+            Ok(doc)
+        }
+    }
+
+    #[test]
+    fn code_symbols_are_fixed_only_in_code_mode() {
+        let image = DynamicImage::new_rgb8(200, 40);
+        let config = crate::config::AppConfig::default();
+        let read = |mode| {
+            let doc = recognize_prepared(&FixedText, &image, &crate::image_orientation::Orientation::Upright, &config, mode).unwrap();
+            doc.lines[0].text.clone()
+        };
+        assert_eq!(read(OcrMode::Code), "file = 100;");
+        assert_eq!(read(OcrMode::Text), "fi1e = 1O0;");
+    }
+
+    #[test]
+    fn code_symbols_leave_words_and_literals_alone() {
+        for text in ["I said OK to version 2", "api/v1beta", "0o17 | 0x1F", "l10n i18n md5", "l0g x1y"] {
+            assert_eq!(fix_code_symbols(text), text);
+        }
+    }
+
+    #[test]
+    fn code_indentation_follows_line_offsets() {
+        let mut doc = code_document(&[
+            ("fn main() {", 10.0, 10.0),
+            // The engine split this row at the gap before "1;"
+            ("let x =", 50.0, 30.0),
+            ("1;", 130.0, 31.0),
+            ("if x > 0 {", 50.0, 70.0),
+            ("print(x);", 91.0, 90.0),
+            ("}", 49.0, 110.0),
+            ("}", 10.0, 130.0),
+        ]);
+        format_code(&mut doc);
+
+        let code = "fn main() {\n    let x = 1;\n\n    if x > 0 {\n        print(x);\n    }\n}";
+        assert_eq!(doc.paragraphs.len(), 1);
+        assert_eq!(doc.paragraphs[0].kind, ParagraphKind::Code);
+        assert_eq!(doc.paragraphs[0].text, code);
+        assert_eq!(doc.lines.len(), 6);
+        assert_eq!(doc.lines[1].text, "    let x = 1;");
+    }
+
+    #[test]
+    fn code_without_indentation_stays_flush() {
+        let mut doc = code_document(&[("a", 10.0, 10.0), ("b", 10.0, 30.0), ("c", 10.0, 50.0)]);
+        format_code(&mut doc);
+        assert_eq!(doc.paragraphs[0].text, "a\nb\nc");
+
+        let mut single = code_document(&[("x;", 10.0, 10.0)]);
+        format_code(&mut single);
+        assert_eq!(single.paragraphs[0].text, "x;");
+
+        let mut empty = code_document(&[("  ", 10.0, 10.0)]);
+        format_code(&mut empty);
+        assert!(empty.lines.is_empty() && empty.paragraphs.is_empty());
+    }
+
+    /// Fills columns `x0..x1` with `background` and rows of `ink` glyph
+    /// strokes, like lines of text, leaving a blank margin around them.
+    fn text_block(image: &mut GrayImage, x0: u32, x1: u32, background: u8, ink: u8) {
+        for y in 0..image.height() {
+            for x in x0..x1 {
+                let glyph = y >= 10 && y + 10 < image.height() && x >= x0 + 10 && x + 10 < x1 && y % 16 < 8 && x % 7 < 4;
+                image.put_pixel(x, y, Luma([if glyph { ink } else { background }]));
+            }
+        }
+    }
+
+    fn luma(image: &DynamicImage, x: u32, y: u32) -> u8 {
+        image.to_luma8().get_pixel(x, y).0[0]
+    }
+
+    #[test]
+    fn light_on_dark_is_inverted() {
+        let mut gray = GrayImage::new(300, 120);
+        text_block(&mut gray, 0, 300, 30, 220);
+        let image = DynamicImage::ImageLuma8(gray);
+
+        let (out, polarity) = normalize_polarity(&image);
+        assert_eq!(polarity, Polarity::Dark);
+        assert_eq!(luma(&out, 2, 2), 255 - 30);
+        assert_eq!(luma(&out, 14, 16), 255 - 220);
+    }
+
+    #[test]
+    fn dark_on_light_is_left_alone() {
+        let mut gray = GrayImage::new(300, 120);
+        text_block(&mut gray, 0, 300, 240, 20);
+        let image = DynamicImage::ImageLuma8(gray);
+
+        let (out, polarity) = normalize_polarity(&image);
+        assert_eq!(polarity, Polarity::Light);
+        assert_eq!(out.to_luma8().as_raw(), image.to_luma8().as_raw());
+    }
+
+    #[test]
+    fn mixed_panels_invert_only_dark_tiles() {
+        // A dark sidebar two tiles wide next to a light editor two tiles wide
+        let mut gray = GrayImage::new(4 * POLARITY_TILE, 2 * POLARITY_TILE);
+        text_block(&mut gray, 0, 2 * POLARITY_TILE, 35, 210);
+        text_block(&mut gray, 2 * POLARITY_TILE, 4 * POLARITY_TILE, 245, 25);
+        let image = DynamicImage::ImageLuma8(gray);
+
+        let (out, polarity) = normalize_polarity(&image);
+        assert_eq!(polarity, Polarity::Mixed(0.5));
+        // Sidebar flipped to dark-on-light, editor untouched
+        assert_eq!(luma(&out, 2, 2), 255 - 35);
+        assert_eq!(luma(&out, 14, 16), 255 - 210);
+        let editor_x = 2 * POLARITY_TILE;
+        assert_eq!(luma(&out, editor_x + 2, 2), 245);
+        assert_eq!(luma(&out, editor_x + 14, 16), 25);
+    }
+}
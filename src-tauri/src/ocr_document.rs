@@ -0,0 +1,148 @@
+use serde::Serialize;
+
+use crate::barcode::DecodedCode;
+use crate::ocr_scoring::CandidateScore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Axis-aligned box as a clockwise polygon starting at the top-left corner.
+pub fn rect_polygon(x: f32, y: f32, width: f32, height: f32) -> Vec<Point> {
+    vec![
+        Point { x, y },
+        Point { x: x + width, y },
+        Point { x: x + width, y: y + height },
+        Point { x, y: y + height },
+    ]
+}
+
+/// (min_x, min_y, max_x, max_y) of a polygon, or `None` when it is empty.
+pub fn polygon_bounds(polygon: &[Point]) -> Option<(f32, f32, f32, f32)> {
+    let first = polygon.first()?;
+    Some(polygon.iter().fold((first.x, first.y, first.x, first.y), |(x0, y0, x1, y1), p| {
+        (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y))
+    }))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrWord {
+    pub text: String,
+    pub polygon: Vec<Point>,
+    pub confidence: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrLine {
+    pub text: String,
+    /// Empty for engines that only report whole lines (PaddleOCR).
+    pub words: Vec<OcrWord>,
+    pub polygon: Vec<Point>,
+    pub confidence: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParagraphKind {
+    Body,
+    Heading,
+    ListItem,
+    /// A whole code listing with its indentation and blank lines.
+    Code,
+}
+
+/// Lines regrouped into reading units by `ocr_layout`.
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrParagraph {
+    pub text: String,
+    pub kind: ParagraphKind,
+    /// Indices into `OcrDocument::lines`, in reading order.
+    pub lines: Vec<usize>,
+    pub polygon: Vec<Point>,
+}
+
+/// Recognition output of a single engine run. Coordinates are pixels of the
+/// image handed to `run_ocr` unless a caller remaps them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OcrDocument {
+    pub engine: String,
+    pub language: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub lines: Vec<OcrLine>,
+    /// Filled in by `run_ocr` for the result it returns.
+    pub score: Option<CandidateScore>,
+    /// Degrees the capture was rotated clockwise before recognition (deskew
+    /// or a quarter turn). Boxes are already mapped back onto the capture.
+    pub rotation: f32,
+    /// Recognized as top-to-bottom CJK columns; each line is one column.
+    pub vertical: bool,
+    /// Empty unless paragraph reconstruction ran.
+    pub paragraphs: Vec<OcrParagraph>,
+    /// QR codes and barcodes in the capture, when decoding ran.
+    pub codes: Vec<DecodedCode>,
+}
+
+impl OcrDocument {
+    pub fn new(engine: &str, width: u32, height: u32) -> Self {
+        Self {
+            engine: engine.to_string(),
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Plain text, one detected line per row.
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|l| l.text.as_str())
+            .filter(|t| !t.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Paragraph text when paragraphs were reconstructed, one per row;
+    /// otherwise the same as `text`.
+    pub fn reading_text(&self) -> String {
+        if self.paragraphs.is_empty() {
+            return self.text();
+        }
+        self.paragraphs.iter().map(|p| p.text.as_str()).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Applies `p * factor + offset` to every polygon, e.g. to undo an upscale
+    /// or to move crop-relative boxes into screen space.
+    pub fn transform(&mut self, factor: f32, offset_x: f32, offset_y: f32) {
+        self.map_points(|p| Point { x: p.x * factor + offset_x, y: p.y * factor + offset_y });
+        self.width = (self.width as f32 * factor).round() as u32;
+        self.height = (self.height as f32 * factor).round() as u32;
+    }
+
+    /// Applies `map` to every line and word polygon point.
+    pub fn map_points(&mut self, map: impl Fn(Point) -> Point) {
+        for line in &mut self.lines {
+            for p in line.polygon.iter_mut() {
+                *p = map(*p);
+            }
+            for word in &mut line.words {
+                for p in word.polygon.iter_mut() {
+                    *p = map(*p);
+                }
+            }
+        }
+        for paragraph in &mut self.paragraphs {
+            for p in paragraph.polygon.iter_mut() {
+                *p = map(*p);
+            }
+        }
+        for code in &mut self.codes {
+            for p in code.polygon.iter_mut() {
+                *p = map(*p);
+            }
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use crate::text_normalize::{is_cjk, is_unspaced};
+use crate::ocr_document::{polygon_bounds, rect_polygon, OcrDocument, OcrParagraph, ParagraphKind};
+
+/// A visual row: one or more engine lines that sit side by side.
+struct Row {
+    text: String,
+    lines: Vec<usize>,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl Row {
+    fn height(&self) -> f32 {
+        self.y1 - self.y0
+    }
+}
+
+/// A paragraph being built, with the bounds of the rows merged into it so far.
+struct Block {
+    paragraph: OcrParagraph,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    /// Left edge of the first row; later rows align against it.
+    left: f32,
+}
+
+/// Groups lines into paragraphs using their boxes: wrapped lines of one block
+/// are merged, while headings, list items and short closing lines start a new
+/// paragraph. Vertical (column) documents are left alone.
+pub fn paragraphs(doc: &OcrDocument) -> Vec<OcrParagraph> {
+    if doc.vertical {
+        return Vec::new();
+    }
+    let rows = rows(doc);
+    if rows.is_empty() {
+        return Vec::new();
+    }
+
+    let mut heights: Vec<f32> = rows.iter().map(Row::height).collect();
+    heights.sort_by(f32::total_cmp);
+    let body_height = heights[heights.len() / 2].max(1.0);
+    let right_margin = rows.iter().map(|r| r.x1).fold(f32::MIN, f32::max);
+
+    let mut blocks: Vec<Block> = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let kind = classify(row, body_height);
+        let prev = i.checked_sub(1).map(|p| &rows[p]);
+
+        let continues = match (prev, blocks.last()) {
+            (Some(prev), Some(block)) => {
+                block.paragraph.kind != ParagraphKind::Heading
+                    && kind == ParagraphKind::Body
+                    && continues_paragraph(prev, row, block.left, body_height, right_margin)
+            }
+            _ => false,
+        };
+
+        if continues {
+            let block = blocks.last_mut().unwrap();
+            block.paragraph.text = join_wrapped(&block.paragraph.text, &row.text);
+            block.paragraph.lines.extend(&row.lines);
+            block.x0 = block.x0.min(row.x0);
+            block.y0 = block.y0.min(row.y0);
+            block.x1 = block.x1.max(row.x1);
+            block.y1 = block.y1.max(row.y1);
+        } else {
+            let paragraph = OcrParagraph { text: row.text.clone(), kind, lines: row.lines.clone(), polygon: Vec::new() };
+            blocks.push(Block { paragraph, x0: row.x0, y0: row.y0, x1: row.x1, y1: row.y1, left: row.x0 });
+        }
+    }
+
+    blocks
+        .into_iter()
+        .map(|block| {
+            let mut para = block.paragraph;
+            para.polygon = rect_polygon(block.x0, block.y0, block.x1 - block.x0, block.y1 - block.y0);
+            para
+        })
+        .collect()
+}
+
+/// Merges engine lines that share a baseline (Paddle splits a row at wide
+/// gaps) and sorts rows top to bottom.
+fn rows(doc: &OcrDocument) -> Vec<Row> {
+    let boxed = doc
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !l.text.trim().is_empty())
+        .filter_map(|(i, l)| Some((i, polygon_bounds(&l.polygon)?)));
+
+    group_rows(boxed)
+        .into_iter()
+        .map(|group| {
+            let (x0, y0, x1, y1) = group.bounds;
+            let text = group.members.iter().fold(String::new(), |acc, i| join_words(&acc, doc.lines[*i].text.trim()));
+            Row { text, lines: group.members, x0, y0, x1, y1 }
+        })
+        .collect()
+}
+
+/// Boxes that `group_rows` put on one visual row.
+pub(crate) struct RowGroup {
+    /// The caller's indices, left to right.
+    pub members: Vec<usize>,
+    /// `(x0, y0, x1, y1)` around every member.
+    pub bounds: (f32, f32, f32, f32),
+}
+
+/// Clusters `(index, bounds)` boxes into rows, top to bottom: a box joins the
+/// row above when they overlap vertically by more than half the shorter
+/// height. Paragraphs, code mode and tables all split rows with this.
+pub(crate) fn group_rows(boxes: impl IntoIterator<Item = (usize, (f32, f32, f32, f32))>) -> Vec<RowGroup> {
+    let mut boxes: Vec<_> = boxes.into_iter().collect();
+    boxes.sort_by(|a, b| a.1 .1.total_cmp(&b.1 .1));
+
+    let mut rows: Vec<(Vec<(usize, f32)>, (f32, f32, f32, f32))> = Vec::new();
+    for (i, (x0, y0, x1, y1)) in boxes {
+        match rows.last_mut() {
+            Some((members, r)) if r.3.min(y1) - r.1.max(y0) > 0.5 * (r.3 - r.1).min(y1 - y0) => {
+                members.push((i, x0));
+                *r = (r.0.min(x0), r.1.min(y0), r.2.max(x1), r.3.max(y1));
+            }
+            _ => rows.push((vec![(i, x0)], (x0, y0, x1, y1))),
+        }
+    }
+
+    rows.into_iter()
+        .map(|(mut members, bounds)| {
+            members.sort_by(|a, b| a.1.total_cmp(&b.1));
+            RowGroup { members: members.into_iter().map(|(i, _)| i).collect(), bounds }
+        })
+        .collect()
+}
+
+fn classify(row: &Row, body_height: f32) -> ParagraphKind {
+    if is_list_item(&row.text) {
+        ParagraphKind::ListItem
+    } else if row.height() > body_height * 1.3 && row.text.chars().count() < 80 {
+        ParagraphKind::Heading
+    } else {
+        ParagraphKind::Body
+    }
+}
+
+/// Bullets, "1." / "1)" / "(1)" / "a)" numbering, and circled or CJK ordinals.
+fn is_list_item(text: &str) -> bool {
+    let text = text.trim_start();
+    let Some(first) = text.chars().next() else { return false };
+    if matches!(first, '•' | '·' | '●' | '○' | '■' | '□' | '◆' | '▪' | '‣' | '–' | '—' | '①'..='⑳') {
+        return true;
+    }
+    if let Some(rest) = text.strip_prefix(['-', '*', '+']) {
+        return rest.starts_with(' ');
+    }
+
+    let rest = text.trim_start_matches(['(', '（']);
+    let marker_len = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || matches!(c, '一' | '二' | '三' | '四' | '五' | '六' | '七' | '八' | '九' | '十'))
+        .count();
+    let marker_len = if marker_len == 0 && rest.chars().next().is_some_and(|c| c.is_ascii_lowercase()) { 1 } else { marker_len };
+    if marker_len == 0 || marker_len > 3 {
+        return false;
+    }
+    let after: String = rest.chars().skip(marker_len).take(2).collect();
+    let mut after = after.chars();
+    match after.next() {
+        Some(')' | '）' | '、') => true,
+        // "1. Item" but not "3.14" or "e.g."
+        Some('.' | '．') => after.next().is_some_and(|c| c.is_whitespace() || !c.is_ascii()),
+        _ => false,
+    }
+}
+
+/// Whether `row` reads as the wrapped continuation of `prev`.
+fn continues_paragraph(prev: &Row, row: &Row, para_left: f32, body_height: f32, right_margin: f32) -> bool {
+    let gap = row.y0 - prev.y1;
+    let similar_height = (row.height() / prev.height().max(1.0) - 1.0).abs() < 0.3;
+    // First lines are often indented, so compare against the paragraph's left
+    // edge and allow a couple of characters of slack
+    let aligned = (row.x0 - para_left).abs() < body_height * 2.0 || (row.x0 - prev.x0).abs() < body_height * 0.5;
+    // A line that stops well short of the block's right edge ends its paragraph
+    let prev_full = right_margin - prev.x1 < body_height * 3.0;
+    gap < body_height * 0.9 && similar_height && aligned && prev_full
+}
+
+/// Joins a wrapped line onto its paragraph: undoes end-of-line hyphenation,
+/// keeps the hyphen of compounds like "well-Known", and never puts a space
+/// next to Chinese or Japanese text.
+fn join_wrapped(paragraph: &str, next: &str) -> String {
+    let mut chars = paragraph.chars().rev();
+    let hyphenated = chars.next() == Some('-') && chars.next().is_some_and(|c| c.is_alphabetic() && !is_cjk(c));
+    if hyphenated {
+        if next.chars().next().is_some_and(|c| c.is_lowercase()) {
+            return format!("{}{}", &paragraph[..paragraph.len() - 1], next);
+        }
+        return format!("{}{}", paragraph, next);
+    }
+    join_words(paragraph, next)
+}
+
+fn join_words(left: &str, right: &str) -> String {
+    let (Some(a), Some(b)) = (left.chars().last(), right.chars().next()) else {
+        return format!("{}{}", left, right);
+    };
+    if is_unspaced(a) || is_unspaced(b) {
+        format!("{}{}", left, right)
+    } else {
+        format!("{} {}", left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr_document::OcrLine;
+
+    /// A document from `(text, x, y, width, height)` line boxes.
+    fn document(lines: &[(&str, f32, f32, f32, f32)]) -> OcrDocument {
+        let mut doc = OcrDocument::new("test", 500, 400);
+        doc.lines = lines
+            .iter()
+            .map(|&(text, x, y, w, h)| OcrLine { text: text.to_string(), words: Vec::new(), polygon: rect_polygon(x, y, w, h), confidence: None })
+            .collect();
+        doc
+    }
+
+    fn summary(paragraphs: &[OcrParagraph]) -> Vec<(ParagraphKind, &str)> {
+        paragraphs.iter().map(|p| (p.kind, p.text.as_str())).collect()
+    }
+
+    #[test]
+    fn wrapped_lines_are_joined() {
+        let doc = document(&[
+            ("Screenshots are recognized as soon as", 10.0, 10.0, 400.0, 20.0),
+            ("the selection is released, and the text", 10.0, 36.0, 400.0, 20.0),
+            ("is copied.", 10.0, 62.0, 90.0, 20.0),
+            // A blank line's gap starts the next paragraph
+            ("截图完成后会自动识别文字并翻译成", 10.0, 110.0, 400.0, 20.0),
+            ("目标语言。", 10.0, 136.0, 100.0, 20.0),
+        ]);
+        let paragraphs = paragraphs(&doc);
+        assert_eq!(
+            summary(&paragraphs),
+            [
+                (ParagraphKind::Body, "Screenshots are recognized as soon as the selection is released, and the text is copied."),
+                (ParagraphKind::Body, "截图完成后会自动识别文字并翻译成目标语言。"),
+            ]
+        );
+        assert_eq!(paragraphs[0].lines, [0, 1, 2]);
+        assert_eq!(polygon_bounds(&paragraphs[0].polygon), Some((10.0, 10.0, 410.0, 82.0)));
+    }
+
+    #[test]
+    fn hyphenated_words_are_rejoined() {
+        let doc = document(&[
+            ("The engine falls back to the next one in the exam-", 10.0, 10.0, 400.0, 20.0),
+            ("ple chain, keeping a well-", 10.0, 36.0, 400.0, 20.0),
+            ("Known order.", 10.0, 62.0, 120.0, 20.0),
+        ]);
+        assert_eq!(
+            summary(&paragraphs(&doc)),
+            [(ParagraphKind::Body, "The engine falls back to the next one in the example chain, keeping a well-Known order.")]
+        );
+    }
+
+    #[test]
+    fn list_items_start_their_own_paragraphs() {
+        let doc = document(&[
+            ("1. Select the region to capture and wait", 10.0, 10.0, 400.0, 20.0),
+            ("for the result", 30.0, 36.0, 140.0, 20.0),
+            ("2. Press Ctrl+C", 10.0, 62.0, 150.0, 20.0),
+            ("• Translation follows automatically", 10.0, 88.0, 330.0, 20.0),
+        ]);
+        assert_eq!(
+            summary(&paragraphs(&doc)),
+            [
+                (ParagraphKind::ListItem, "1. Select the region to capture and wait for the result"),
+                (ParagraphKind::ListItem, "2. Press Ctrl+C"),
+                (ParagraphKind::ListItem, "• Translation follows automatically"),
+            ]
+        );
+    }
+
+    #[test]
+    fn heading_is_kept_apart_from_body() {
+        let doc = document(&[
+            ("Getting started", 10.0, 10.0, 260.0, 32.0),
+            ("Install the application and open the settings", 10.0, 52.0, 400.0, 20.0),
+            ("window from the tray.", 10.0, 78.0, 200.0, 20.0),
+        ]);
+        assert_eq!(
+            summary(&paragraphs(&doc)),
+            [
+                (ParagraphKind::Heading, "Getting started"),
+                (ParagraphKind::Body, "Install the application and open the settings window from the tray."),
+            ]
+        );
+    }
+
+    #[test]
+    fn side_by_side_lines_form_one_row() {
+        let doc = document(&[("right half", 220.0, 10.0, 100.0, 20.0), ("Left half,", 10.0, 11.0, 100.0, 20.0)]);
+        let paragraphs = paragraphs(&doc);
+        assert_eq!(summary(&paragraphs), [(ParagraphKind::Body, "Left half, right half")]);
+        assert_eq!(paragraphs[0].lines, [1, 0]);
+    }
+}
@@ -0,0 +1,327 @@
+use serde::Serialize;
+
+use crate::ocr_document::OcrDocument;
+
+/// Why a candidate won or lost. Every component is in `0.0..=1.0`; `total`
+/// additionally grows with the amount of plausible text, so a clean long
+/// result beats a clean short one but not a noisy long one.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct CandidateScore {
+    /// Share of characters that are letters, digits, CJK or ordinary punctuation.
+    pub char_class: f32,
+    /// Share of Latin words that look like words (known or well-formed).
+    pub dictionary: f32,
+    /// Mean engine confidence, when the engine reports one.
+    pub confidence: Option<f32>,
+    /// How consistently each line sticks to one script.
+    pub script_consistency: f32,
+    pub quality: f32,
+    pub total: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Han,
+    Kana,
+    Hangul,
+    Latin,
+    Cyrillic,
+    Digit,
+    Punctuation,
+    Other,
+}
+
+fn classify(c: char) -> Script {
+    match c {
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}' => Script::Han,
+        '\u{3040}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => Script::Kana,
+        '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' => Script::Hangul,
+        '\u{0400}'..='\u{04FF}' => Script::Cyrillic,
+        // Fullwidth ASCII
+        '\u{FF10}'..='\u{FF19}' => Script::Digit,
+        '\u{FF21}'..='\u{FF3A}' | '\u{FF41}'..='\u{FF5A}' => Script::Latin,
+        c if c.is_ascii_digit() => Script::Digit,
+        c if c.is_alphabetic() && (c.is_ascii() || ('\u{00C0}'..='\u{024F}').contains(&c)) => Script::Latin,
+        c if c.is_ascii_punctuation() => Script::Punctuation,
+        '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF0F}' | '\u{FF1A}'..='\u{FF20}' | '\u{FF5B}'..='\u{FF65}' => Script::Punctuation,
+        '\u{2010}'..='\u{2027}' | '\u{00B7}' | '\u{00A9}' | '\u{00AE}' | '\u{00B0}' | '\u{00D7}' => Script::Punctuation,
+        _ => Script::Other,
+    }
+}
+
+/// Scripts that count as "the same writing system" on one line. Japanese mixes
+/// Han and Kana freely, so those two never count as a switch.
+fn script_family(script: Script) -> Option<u8> {
+    match script {
+        Script::Han | Script::Kana => Some(0),
+        Script::Hangul => Some(1),
+        Script::Latin => Some(2),
+        Script::Cyrillic => Some(3),
+        Script::Digit | Script::Punctuation | Script::Other => None,
+    }
+}
+
+/// Short, very frequent words that fail the shape test or are worth a
+/// guaranteed hit.
+const COMMON_WORDS: &[&str] = &[
+    "a", "i", "an", "as", "at", "be", "by", "do", "go", "he", "if", "in", "is", "it", "me", "my", "no", "of",
+    "ok", "on", "or", "so", "to", "up", "us", "we", "the", "and", "for", "you", "not", "are", "but", "all",
+    "can", "her", "was", "one", "our", "out", "get", "has", "him", "his", "how", "new", "now", "see", "two",
+    "way", "who", "did", "its", "let", "say", "she", "too", "use", "www", "http", "https", "com", "api", "url",
+];
+
+/// A word is plausible if it is common or looks like something a person typed:
+/// a sane case pattern, a vowel somewhere, and no long consonant pile-up.
+fn is_plausible_word(word: &str) -> bool {
+    let lower = word.to_lowercase();
+    if COMMON_WORDS.contains(&lower.as_str()) {
+        return true;
+    }
+    let chars: Vec<char> = word.chars().collect();
+    if chars.len() < 2 || chars.len() > 24 {
+        return false;
+    }
+
+    // "word", "Word", "WORD" are fine; "wOrD" is what noise looks like.
+    // camelCase identifiers get a pass as long as they start lowercase.
+    let upper = chars.iter().filter(|c| c.is_uppercase()).count();
+    let case_ok = upper == 0
+        || upper == chars.len()
+        || (upper == 1 && chars[0].is_uppercase())
+        || (chars[0].is_lowercase() && upper * 3 <= chars.len());
+    if !case_ok {
+        return false;
+    }
+
+    let is_vowel = |c: &char| "aeiouyAEIOUY".contains(*c) || !c.is_ascii();
+    if !chars.iter().any(is_vowel) {
+        // Acronyms like "HTTP" or "CSS" have no vowels
+        return upper == chars.len() && chars.len() <= 5;
+    }
+
+    let mut consonant_run = 0;
+    let mut repeat_run = 1;
+    for (i, c) in chars.iter().enumerate() {
+        consonant_run = if is_vowel(c) { 0 } else { consonant_run + 1 };
+        repeat_run = if i > 0 && chars[i - 1] == *c { repeat_run + 1 } else { 1 };
+        if consonant_run > 4 || repeat_run > 2 {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn score_document(doc: &OcrDocument) -> CandidateScore {
+    // Word confidences (Tesseract) where present, else line confidences (Paddle), weighted by length
+    let mut weighted = 0.0f32;
+    let mut weight = 0.0f32;
+    for line in &doc.lines {
+        let parts: Vec<(f32, usize)> = if line.words.iter().any(|w| w.confidence.is_some()) {
+            line.words.iter().filter_map(|w| Some((w.confidence?, w.text.chars().count()))).collect()
+        } else {
+            line.confidence.map(|c| (c, line.text.chars().count())).into_iter().collect()
+        };
+        for (conf, len) in parts {
+            weighted += conf.clamp(0.0, 1.0) * len as f32;
+            weight += len as f32;
+        }
+    }
+    let confidence = (weight > 0.0).then(|| weighted / weight);
+
+    score_parts(&doc.text(), confidence)
+}
+
+fn score_parts(text: &str, confidence: Option<f32>) -> CandidateScore {
+    let chars: Vec<Script> = text.chars().filter(|c| !c.is_whitespace()).map(classify).collect();
+    if chars.is_empty() {
+        return CandidateScore { confidence, ..Default::default() };
+    }
+
+    let other = chars.iter().filter(|s| **s == Script::Other).count();
+    let punctuation = chars.iter().filter(|s| **s == Script::Punctuation).count();
+    // Punctuation is normal, but a line made mostly of it is noise
+    let excess_punct = punctuation.saturating_sub(chars.len() / 3);
+    let char_class = 1.0 - (other + excess_punct) as f32 / chars.len() as f32;
+
+    let words: Vec<&str> = text
+        .split(|c: char| classify(c) != Script::Latin)
+        .filter(|w| !w.is_empty())
+        .collect();
+    let dictionary = if words.is_empty() {
+        1.0
+    } else {
+        let letters: usize = words.iter().map(|w| w.chars().count()).sum();
+        let good: usize = words.iter().filter(|w| is_plausible_word(w)).map(|w| w.chars().count()).sum();
+        good as f32 / letters as f32
+    };
+
+    let script_consistency = script_consistency(text);
+
+    let mut quality = 0.30 * char_class + 0.25 * dictionary + 0.20 * script_consistency;
+    let mut total_weight = 0.75;
+    if let Some(conf) = confidence {
+        quality += 0.35 * conf;
+        total_weight += 0.35;
+    }
+    let quality = (quality / total_weight).clamp(0.0, 1.0);
+
+    // Only text that passes the class check earns length credit
+    let useful = chars.len() - other;
+    let total = quality * quality * (1.0 + useful as f32).ln();
+
+    CandidateScore { char_class, dictionary, confidence, script_consistency, quality, total }
+}
+
+/// Per line, the share of letters belonging to the line's dominant script,
+/// minus a penalty for switching scripts inside a single token ("中a文").
+fn script_consistency(text: &str) -> f32 {
+    let mut sum = 0.0;
+    let mut letters_total = 0usize;
+
+    for line in text.lines() {
+        let families: Vec<u8> = line.chars().filter_map(|c| script_family(classify(c))).collect();
+        if families.is_empty() {
+            continue;
+        }
+        let mut counts = [0usize; 4];
+        for f in &families {
+            counts[*f as usize] += 1;
+        }
+        let dominant = *counts.iter().max().unwrap_or(&0);
+
+        // Mixed-script lines are common ("使用 Rust 编写"), so only a minority
+        // script that is interleaved within tokens counts against the line.
+        let mut switches = 0usize;
+        for token in line.split_whitespace() {
+            let fams: Vec<u8> = token.chars().filter_map(|c| script_family(classify(c))).collect();
+            switches += fams.windows(2).filter(|w| w[0] != w[1]).count();
+        }
+        let minority = families.len() - dominant;
+        let line_score = 1.0 - (switches.min(minority) as f32 / families.len() as f32);
+
+        sum += line_score * families.len() as f32;
+        letters_total += families.len();
+    }
+
+    if letters_total == 0 {
+        1.0
+    } else {
+        sum / letters_total as f32
+    }
+}
+
+/// Returns the index and score of the best-scoring document. Ties go to the
+/// earlier candidate, so callers should list their preferred strategy first.
+pub fn pick_best(candidates: &[OcrDocument]) -> Option<(usize, CandidateScore)> {
+    let mut best: Option<(usize, CandidateScore)> = None;
+    for (i, doc) in candidates.iter().enumerate() {
+        let score = score_document(doc);
+        println!(
+            "ocr_scoring: #{} [{}] total={:.3} quality={:.2} class={:.2} dict={:.2} conf={:?} script={:.2}",
+            i,
+            doc.language.as_deref().unwrap_or(&doc.engine),
+            score.total,
+            score.quality,
+            score.char_class,
+            score.dictionary,
+            score.confidence,
+            score.script_consistency
+        );
+        if best.is_none_or(|(_, b)| score.total > b.total) {
+            best = Some((i, score));
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score_text(text: &str) -> CandidateScore {
+        score_parts(text, None)
+    }
+
+    /// Real-world shaped outputs, one per line: script, description, what a
+    /// good engine produced, and what a worse pass or language produced on the
+    /// same capture.
+    const CORPUS: &str = include_str!("../tests/fixtures/ocr_scoring_corpus.tsv");
+
+    fn corpus() -> Vec<(&'static str, &'static str, &'static str)> {
+        CORPUS
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                assert_eq!(fields.len(), 4, "malformed corpus line: {}", line);
+                (fields[1], fields[2], fields[3])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn corpus_prefers_good_candidate() {
+        let corpus = corpus();
+        for script in ["cjk", "latin", "mixed"] {
+            assert!(CORPUS.lines().any(|line| line.starts_with(script)), "corpus has no {} pairs", script);
+        }
+        for (name, good, bad) in corpus {
+            let good_score = score_text(good);
+            let bad_score = score_text(bad);
+            assert!(
+                good_score.total > bad_score.total,
+                "{}: good {:?} should beat bad {:?}",
+                name,
+                good_score,
+                bad_score
+            );
+        }
+    }
+
+    #[test]
+    fn longer_clean_text_wins() {
+        let short = score_text("Hello");
+        let long = score_text("Hello world, this is a longer sentence.");
+        assert!(long.total > short.total);
+    }
+
+    #[test]
+    fn empty_text_scores_zero() {
+        assert_eq!(score_text("").total, 0.0);
+        assert_eq!(score_text("   \n ").total, 0.0);
+    }
+
+    #[test]
+    fn confidence_breaks_ties() {
+        use crate::ocr_document::OcrLine;
+
+        let doc = |confidence: f32| {
+            let mut doc = OcrDocument::new("test", 100, 20);
+            doc.lines.push(OcrLine {
+                text: "Open file".to_string(),
+                words: Vec::new(),
+                polygon: Vec::new(),
+                confidence: Some(confidence),
+            });
+            doc
+        };
+        let candidates = [doc(0.40), doc(0.95)];
+        assert_eq!(pick_best(&candidates).map(|(i, _)| i), Some(1));
+    }
+
+    #[test]
+    fn kana_and_han_do_not_count_as_switches() {
+        assert_eq!(script_consistency("日本語のテキスト"), 1.0);
+        assert!(script_consistency("日a本b語c") < 1.0);
+    }
+
+    #[test]
+    fn word_shapes() {
+        for word in ["hello", "Hello", "HELLO", "HTTP", "camelCase", "a", "I"] {
+            assert!(is_plausible_word(word), "{} should be plausible", word);
+        }
+        for word in ["hElLo", "xkcdqz", "aaaa", "q"] {
+            assert!(!is_plausible_word(word), "{} should be implausible", word);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use serde::Serialize;
+
+use crate::ocr_document::{polygon_bounds, OcrDocument, OcrLine};
+use crate::ocr_layout::group_rows;
+use crate::text_normalize::is_unspaced;
+
+/// A grid recovered from cell boxes. `rows[r][c]` is empty where a row has
+/// no text in column `c`.
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrTable {
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableFormat {
+    Tsv,
+    Csv,
+    Markdown,
+}
+
+impl TableFormat {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "tsv" => Ok(TableFormat::Tsv),
+            "csv" => Ok(TableFormat::Csv),
+            "markdown" | "md" => Ok(TableFormat::Markdown),
+            other => Err(format!("Unknown table format '{}'", other)),
+        }
+    }
+}
+
+/// A box with its text; one table cell candidate.
+struct Cell {
+    text: String,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+}
+
+/// Clusters cell boxes into rows and columns. PaddleOCR boxes each cell on
+/// its own; engines that return whole rows (Windows OCR, Tesseract) are split
+/// into cells at wide gaps between words. Returns `None` unless at least two
+/// rows share at least two columns.
+pub fn detect(doc: &OcrDocument) -> Option<OcrTable> {
+    if doc.vertical {
+        return None;
+    }
+    let rows = rows(doc.lines.iter().flat_map(cells).collect());
+    if rows.len() < 2 {
+        return None;
+    }
+
+    let bands = columns(&rows);
+    if bands.len() < 2 {
+        return None;
+    }
+
+    let mut grid = vec![vec![String::new(); bands.len()]; rows.len()];
+    for (r, row) in rows.iter().enumerate() {
+        for cell in row {
+            let c = column_of(cell, &bands);
+            grid[r][c] = join(&grid[r][c], &cell.text);
+        }
+    }
+
+    // Mostly single-cell rows are prose next to a stray label, not a table
+    let multi = grid.iter().filter(|row| row.iter().filter(|c| !c.is_empty()).count() >= 2).count();
+    if multi < 2 || (multi as f32) < grid.len() as f32 * 0.6 {
+        return None;
+    }
+    Some(OcrTable { rows: grid })
+}
+
+/// Splits a line into cells. Lines without word boxes are one cell.
+fn cells(line: &OcrLine) -> Vec<Cell> {
+    let text = line.text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let Some((x0, y0, x1, y1)) = polygon_bounds(&line.polygon) else { return Vec::new() };
+    if line.words.is_empty() {
+        return vec![Cell { text: text.to_string(), x0, y0, x1, y1 }];
+    }
+
+    // Gaps between words of one cell stay under roughly a character width
+    let max_gap = (y1 - y0).max(1.0) * 1.2;
+    let mut out: Vec<Cell> = Vec::new();
+    for word in &line.words {
+        let Some((wx0, wy0, wx1, wy1)) = polygon_bounds(&word.polygon) else { continue };
+        match out.last_mut() {
+            Some(cell) if wx0 - cell.x1 < max_gap => {
+                cell.text = join(&cell.text, word.text.trim());
+                cell.x1 = cell.x1.max(wx1);
+                cell.y0 = cell.y0.min(wy0);
+                cell.y1 = cell.y1.max(wy1);
+            }
+            _ => out.push(Cell { text: word.text.trim().to_string(), x0: wx0, y0: wy0, x1: wx1, y1: wy1 }),
+        }
+    }
+    // One cell means the line had no gaps; keep the engine's own text for it
+    if out.len() == 1 {
+        out[0].text = text.to_string();
+    }
+    out
+}
+
+/// Groups cells into rows the same way paragraphs and code mode do, top to
+/// bottom, each row sorted left to right.
+fn rows(cells: Vec<Cell>) -> Vec<Vec<Cell>> {
+    let groups = group_rows(cells.iter().enumerate().map(|(i, c)| (i, (c.x0, c.y0, c.x1, c.y1))));
+    let mut slots: Vec<Option<Cell>> = cells.into_iter().map(Some).collect();
+    groups
+        .into_iter()
+        .map(|g| g.members.iter().filter_map(|&i| slots[i].take()).collect())
+        .collect()
+}
+
+/// Column bands: the union of horizontally overlapping cell extents. Only
+/// rows with several cells contribute, so a title spanning the whole table
+/// does not fuse every column into one.
+fn columns(rows: &[Vec<Cell>]) -> Vec<(f32, f32)> {
+    let mut extents: Vec<(f32, f32)> = rows
+        .iter()
+        .filter(|row| row.len() >= 2)
+        .flat_map(|row| row.iter().map(|c| (c.x0, c.x1)))
+        .collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut bands: Vec<(f32, f32)> = Vec::new();
+    for (x0, x1) in extents {
+        match bands.last_mut() {
+            Some(band) if x0 < band.1 => band.1 = band.1.max(x1),
+            _ => bands.push((x0, x1)),
+        }
+    }
+    bands
+}
+
+/// The first band a cell overlaps (cells spanning several columns, such as
+/// captions, start where they start), or the nearest one when it overlaps none.
+fn column_of(cell: &Cell, bands: &[(f32, f32)]) -> usize {
+    if let Some(i) = bands.iter().position(|&(x0, x1)| x1.min(cell.x1) - x0.max(cell.x0) > 0.0) {
+        return i;
+    }
+    let center = (cell.x0 + cell.x1) / 2.0;
+    let distance = |&(x0, x1): &(f32, f32)| (center - (x0 + x1) / 2.0).abs();
+    (0..bands.len()).min_by(|&a, &b| distance(&bands[a]).total_cmp(&distance(&bands[b]))).unwrap_or(0)
+}
+
+fn join(left: &str, right: &str) -> String {
+    match (left.chars().last(), right.chars().next()) {
+        (Some(a), Some(b)) if !is_unspaced(a) && !is_unspaced(b) => format!("{} {}", left, right),
+        _ => format!("{}{}", left, right),
+    }
+}
+
+impl OcrTable {
+    pub fn format(&self, format: TableFormat) -> String {
+        match format {
+            TableFormat::Tsv => self.to_tsv(),
+            TableFormat::Csv => self.to_csv(),
+            TableFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// Tab-separated, as spreadsheets expect on paste.
+    pub fn to_tsv(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(|c| c.replace(['\t', '\n'], " ")).collect::<Vec<_>>().join("\t"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// RFC 4180: fields with separators, quotes or line breaks are quoted.
+    pub fn to_csv(&self) -> String {
+        let field = |c: &String| {
+            if c.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", c.replace('"', "\"\""))
+            } else {
+                c.clone()
+            }
+        };
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(field).collect::<Vec<_>>().join(","))
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    }
+
+    /// GitHub-flavored table; the first row becomes the header.
+    pub fn to_markdown(&self) -> String {
+        let line = |row: &[String]| {
+            let cells: Vec<String> = row.iter().map(|c| c.replace('|', "\\|").replace('\n', " ")).collect();
+            format!("| {} |", cells.join(" | "))
+        };
+        let Some((header, body)) = self.rows.split_first() else { return String::new() };
+        let mut out = vec![line(header), format!("|{}", " --- |".repeat(header.len()))];
+        out.extend(body.iter().map(|row| line(row)));
+        out.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr_document::{rect_polygon, OcrWord};
+
+    fn table(rows: &[&[&str]]) -> OcrTable {
+        OcrTable { rows: rows.iter().map(|row| row.iter().map(|c| c.to_string()).collect()).collect() }
+    }
+
+    /// One line per cell, as PaddleOCR reports them.
+    fn cell_line(text: &str, x: f32, y: f32, width: f32) -> OcrLine {
+        OcrLine { text: text.to_string(), words: Vec::new(), polygon: rect_polygon(x, y, width, 20.0), confidence: None }
+    }
+
+    #[test]
+    fn csv_quotes_separators_quotes_and_newlines() {
+        let table = table(&[&["name", "note"], &["Smith, J.", "says \"hi\""], &["two\nlines", "plain"]]);
+        assert_eq!(table.to_csv(), "name,note\r\n\"Smith, J.\",\"says \"\"hi\"\"\"\r\n\"two\nlines\",plain");
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        let table = table(&[&["expr", "value"], &["a | b", "1"], &["x\ny", ""]]);
+        assert_eq!(table.to_markdown(), "| expr | value |\n| --- | --- |\n| a \\| b | 1 |\n| x y |  |");
+    }
+
+    #[test]
+    fn tsv_flattens_tabs_and_newlines() {
+        let table = table(&[&["a\tb", "c\nd"]]);
+        assert_eq!(table.to_tsv(), "a b\tc d");
+    }
+
+    #[test]
+    fn detects_grid_with_a_missing_cell() {
+        let mut doc = OcrDocument::new("test", 400, 120);
+        doc.lines = vec![
+            cell_line("名称", 10.0, 10.0, 60.0),
+            cell_line("数量", 150.0, 10.0, 60.0),
+            cell_line("单价", 290.0, 10.0, 60.0),
+            cell_line("苹果", 10.0, 40.0, 60.0),
+            cell_line("3", 150.0, 41.0, 15.0),
+            cell_line("2.50", 290.0, 39.0, 45.0),
+            // No quantity on this row
+            cell_line("梨", 10.0, 70.0, 30.0),
+            cell_line("4.00", 290.0, 70.0, 45.0),
+        ];
+        let table = detect(&doc).expect("a 3x3 grid");
+        assert_eq!(table.rows, [["名称", "数量", "单价"], ["苹果", "3", "2.50"], ["梨", "", "4.00"]]);
+    }
+
+    #[test]
+    fn splits_whole_row_lines_at_wide_gaps() {
+        let word = |text: &str, x: f32, width: f32, y: f32| OcrWord {
+            text: text.to_string(),
+            polygon: rect_polygon(x, y, width, 20.0),
+            confidence: None,
+        };
+        let row = |text: &str, words: Vec<OcrWord>, y: f32| OcrLine {
+            text: text.to_string(),
+            words,
+            polygon: rect_polygon(10.0, y, 340.0, 20.0),
+            confidence: None,
+        };
+        let mut doc = OcrDocument::new("test", 400, 80);
+        doc.lines = vec![
+            row("Unit price Total", vec![word("Unit", 10.0, 40.0, 10.0), word("price", 58.0, 50.0, 10.0), word("Total", 290.0, 50.0, 10.0)], 10.0),
+            row("2.50 7.50", vec![word("2.50", 10.0, 45.0, 40.0), word("7.50", 290.0, 45.0, 40.0)], 40.0),
+        ];
+        assert_eq!(detect(&doc).expect("a 2x2 grid").rows, [["Unit price", "Total"], ["2.50", "7.50"]]);
+    }
+
+    #[test]
+    fn prose_is_not_a_table() {
+        let mut doc = OcrDocument::new("test", 400, 80);
+        doc.lines = vec![cell_line("A paragraph of text", 10.0, 10.0, 300.0), cell_line("that wraps once.", 10.0, 36.0, 200.0)];
+        assert!(detect(&doc).is_none());
+    }
+}
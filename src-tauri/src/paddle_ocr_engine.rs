@@ -1,9 +1,19 @@
-use std::process::{Command, Stdio, Child};
+use std::process::{Command, Stdio, Child, ChildStdin, ChildStderr};
+#[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
-use std::io::{Write, BufReader, BufRead};
+use std::collections::VecDeque;
+use std::io::{Write, BufReader, BufRead, Read};
+use std::path::PathBuf;
 use serde::Deserialize;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, Once, RwLock};
+use std::time::{Duration, Instant};
 use once_cell::sync::Lazy;
+use image::DynamicImage;
+
+use crate::config::{resolve_resource_path, AppConfig};
+use crate::ocr_backend::{BackendHealth, OcrBackend, OcrCapabilities};
+use crate::ocr_document::{OcrDocument, OcrLine, Point};
 
 #[derive(Debug, Deserialize)]
 pub struct PaddleResult {
@@ -20,146 +30,825 @@ pub struct PaddleData {
     pub score: f64,
 }
 
-// Global singleton to hold the persistent process
-static OCR_PROCESS: Lazy<Mutex<Option<Child>>> = Lazy::new(|| Mutex::new(None));
-
-fn get_or_spawn_process() -> Result<std::process::Child, String> {
-    // This function acts as a factory. 
-    // It spawns a NEW process. The caller is responsible for putting it into the Mutex if needed, 
-    // or we can just return the handle.
-    // Actually, to interact with a mutex-guarded process, we need to access it IN PLACE.
-    // So this helper might just return a Child.
-    
-    let engine_path = r"C:\Users\Administrator\Desktop\Booboo\ocr-engine\PaddleOCR-json_v1.4.1\PaddleOCR-json.exe";
-    let engine_dir = std::path::Path::new(engine_path).parent().ok_or("Invalid engine path")?;
-    
-    println!("PaddleOCR: Spawning new process...");
-    let child = Command::new(engine_path)
-        .current_dir(engine_dir)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .spawn()
-        .map_err(|e| format!("Failed to spawn PaddleOCR: {}", e))?;
-        
-    Ok(child)
-}
-
-pub fn run_paddle_ocr(image_path: &str) -> Result<String, String> {
-    // Use a lock to ensure only one thread accesses the OCR process at a time
-    let mut process_guard = OCR_PROCESS.lock().map_err(|e| format!("Mutex poisoned: {}", e))?;
-
-    // Check if process exists and is alive; if not, spawn it
-    if process_guard.is_none() {
-        let child = get_or_spawn_process()?;
-        *process_guard = Some(child);
+/// Everything needed to launch the engine, resolved from `AppConfig`.
+/// A running process is restarted when these change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaddleSettings {
+    pub engine_path: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl PaddleSettings {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let engine_path = resolve_resource_path(&config.paddle_engine_path);
+
+        let mut args = Vec::new();
+        if !config.paddle_models_dir.trim().is_empty() {
+            let dir = resolve_resource_path(&config.paddle_models_dir);
+            args.push(format!("--models_path={}", dir.display()));
+        } else if let Some(dir) = default_models_dir(&engine_path) {
+            args.push(format!("--models_path={}", dir.display()));
+        }
+        if !config.paddle_config_path.trim().is_empty() {
+            let file = resolve_resource_path(&config.paddle_config_path);
+            args.push(format!("--config_path={}", file.display()));
+        }
+        args.extend(config.paddle_extra_args.iter().filter(|a| !a.trim().is_empty()).cloned());
+
+        Self { engine_path, args }
+    }
+
+    /// Checks that the configured files exist before we try to spawn anything.
+    pub fn validate(&self, config: &AppConfig) -> Result<(), String> {
+        if !self.engine_path.is_file() {
+            return Err(format!("Engine not found at {}", self.engine_path.display()));
+        }
+        if !config.paddle_models_dir.trim().is_empty() {
+            let dir = resolve_resource_path(&config.paddle_models_dir);
+            if !dir.is_dir() {
+                return Err(format!("Model directory not found at {}", dir.display()));
+            }
+        }
+        if !config.paddle_config_path.trim().is_empty() {
+            let file = resolve_resource_path(&config.paddle_config_path);
+            if !file.is_file() {
+                return Err(format!("Config file not found at {}", file.display()));
+            }
+        }
+        Ok(())
     }
+}
+
+struct PaddleProcess {
+    child: Child,
+    stdin: ChildStdin,
+    // Fed by the stdout reader thread, which lives as long as the process
+    output: mpsc::Receiver<Output>,
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    // Sequence number of the next request; matches the reader's response count
+    next_seq: u64,
+    settings: PaddleSettings,
+    // Cleared once the engine turns out not to understand `image_base64`
+    base64_supported: bool,
+    last_used: Instant,
+}
 
-    let child = process_guard.as_mut().unwrap();
+/// Printed on stdout once the models are loaded and requests are accepted.
+const INIT_BANNER: &str = "OCR init completed";
 
-    // Prepare payload
-    let payload_json = serde_json::json!({
-        "image_path": image_path
-    });
-    let payload_str = payload_json.to_string();
-    println!("Paddle PAYLOAD: {}", payload_str);
+/// Settings of the last engine that came up without printing `INIT_BANNER`.
+/// Restarts of that build only wait `NO_BANNER_GRACE` for it, so warm-up and
+/// the first capture after an idle unload don't sit out the startup timeout.
+static NO_BANNER: Mutex<Option<PaddleSettings>> = Mutex::new(None);
 
-    // Send to stdin
-    if let Some(stdin) = child.stdin.as_mut() {
-        if let Err(e) = stdin.write_all(payload_str.as_bytes()) {
-            // Write failed? Process might be dead.
-            println!("PaddleOCR: Write failed ({}), killing process.", e);
-            let _ = child.kill();
-            *process_guard = None; // Reset so next call respawns
-            return Err(format!("Failed to write to OCR process: {}", e));
-        }
-        if let Err(e) = stdin.write_all(b"\n") {
-             println!("PaddleOCR: Flush failed ({}), killing process.", e);
-             let _ = child.kill();
-             *process_guard = None;
-             return Err(format!("Failed to flush to OCR process: {}", e));
-        }
-        if let Err(e) = stdin.flush() {
-             println!("PaddleOCR: Flush failed ({}), killing process.", e);
-             let _ = child.kill();
-             *process_guard = None;
-             return Err(format!("Failed to flush stdin: {}", e));
-        }
-    } else {
-        *process_guard = None;
-        return Err("Child process stdin not captured".to_string());
-    }
-
-    // Read from stdout
-    // CRITICAL: We need to read lines until we get a valid JSON result.
-    // The engine might print init messages like "PaddleOCR-json v1.4.1" on startup.
-    if let Some(stdout) = child.stdout.as_mut() {
-        let mut reader = BufReader::new(stdout);
-        let mut line = String::new();
-        
+const NO_BANNER_GRACE: Duration = Duration::from_secs(2);
+
+/// Stderr lines kept for error messages.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// What the stdout reader thread hands to the request side.
+enum Output {
+    Ready,
+    /// The `seq`-th JSON line since the process started.
+    Response { seq: u64, value: serde_json::Value },
+}
+
+/// How the image reaches the engine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageTransport {
+    /// PNG bytes inlined in the request as `image_base64`. No disk I/O.
+    Base64,
+    /// PNG written to a unique temp file and sent as `image_path`.
+    File,
+}
+
+impl ImageTransport {
+    pub fn from_config(config: &AppConfig) -> Self {
+        match config.paddle_image_transport.as_str() {
+            "file" => ImageTransport::File,
+            _ => ImageTransport::Base64,
+        }
+    }
+}
+
+/// A temp PNG with a per-request name, removed when dropped, so concurrent
+/// captures never overwrite each other's input.
+struct TempImage(PathBuf);
+
+static TEMP_IMAGE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+impl TempImage {
+    fn write(png: &[u8]) -> Result<Self, String> {
+        let seq = TEMP_IMAGE_SEQ.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("booboo_paddle_{}_{}.png", std::process::id(), seq));
+        std::fs::write(&path, png).map_err(|e| format!("Failed to write temp image: {}", e))?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempImage {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+enum RequestError {
+    /// The process is unusable (I/O failure, EOF) and must be respawned.
+    Fatal(String),
+    /// No response within the deadline. The process may still be busy and must be killed.
+    Timeout(Duration),
+    /// The engine answered with an error code; the process itself is fine.
+    Engine(String),
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestError::Fatal(e) | RequestError::Engine(e) => write!(f, "{}", e),
+            RequestError::Timeout(d) => write!(f, "PaddleOCR timed out after {:?}", d),
+        }
+    }
+}
+
+/// One engine process slot. The process is spawned lazily and respawned
+/// after a crash or timeout; `in_flight` counts queued + running requests.
+struct Worker {
+    id: usize,
+    process: Mutex<Option<PaddleProcess>>,
+    in_flight: AtomicUsize,
+}
+
+impl Worker {
+    fn shutdown(&self) {
+        if let Ok(mut guard) = self.process.lock() {
+            if let Some(process) = guard.take() {
+                println!("PaddleOCR: Stopping worker {}.", self.id);
+                process.shutdown();
+            }
+        }
+    }
+
+    /// Spawns the process if needed and waits for its init banner.
+    fn warm_up(&self, settings: &PaddleSettings, timeout: Duration) -> Result<(), String> {
+        let mut guard = self.process.lock().map_err(|e| format!("Mutex poisoned: {}", e))?;
+        if guard.as_ref().is_some_and(|p| p.settings == *settings) {
+            return Ok(());
+        }
+        if let Some(old) = guard.take() {
+            old.shutdown();
+        }
+
+        *guard = Some(PaddleProcess::spawn_ready(self.id, settings, timeout)?);
+        Ok(())
+    }
+
+    /// Unloads the model if the process has sat unused for `idle`. Busy
+    /// workers are skipped rather than waited on. The next request restarts
+    /// the process and waits for the model like `warm_up` does.
+    fn unload_if_idle(&self, idle: Duration) {
+        let Ok(mut guard) = self.process.try_lock() else { return };
+        if guard.as_ref().is_some_and(|p| p.last_used.elapsed() >= idle) {
+            println!("PaddleOCR: Worker {} idle for {:?}, unloading.", self.id, idle);
+            if let Some(process) = guard.take() {
+                process.shutdown();
+            }
+        }
+    }
+}
+
+/// Fixed-size set of engine processes. Each request goes to the worker with
+/// the fewest requests in flight; ties rotate so load spreads evenly.
+struct PaddlePool {
+    workers: RwLock<Vec<Arc<Worker>>>,
+    next: AtomicUsize,
+    // 0 disables idle unloading
+    idle_timeout_secs: AtomicU64,
+    reaper: Once,
+    /// (workers, idle_timeout_secs) last applied by `configure`.
+    applied: Mutex<Option<(usize, u64)>>,
+}
+
+/// A claimed slot on a worker; releases it when dropped.
+struct InFlight(Arc<Worker>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl PaddlePool {
+    fn resize(&self, size: usize) {
+        let size = size.max(1);
+        if self.workers.read().unwrap().len() == size {
+            return;
+        }
+
+        let removed = {
+            let mut workers = self.workers.write().unwrap();
+            while workers.len() < size {
+                let id = workers.len();
+                workers.push(Arc::new(Worker { id, process: Mutex::new(None), in_flight: AtomicUsize::new(0) }));
+            }
+            workers.split_off(size)
+        };
+        println!("PaddleOCR: Pool resized to {} worker(s).", size);
+
+        // Removed workers finish whatever they are running before their lock frees up
+        for worker in removed {
+            worker.shutdown();
+        }
+    }
+
+    /// Claims the least busy worker. The count is bumped by a compare-exchange
+    /// against the value the worker was chosen by, so two requests arriving
+    /// together can't both take the same idle worker.
+    fn pick(&self) -> Result<InFlight, String> {
+        let workers = self.workers.read().unwrap();
+        if workers.is_empty() {
+            return Err("PaddleOCR pool has no workers".to_string());
+        }
         loop {
-            line.clear();
-            match reader.read_line(&mut line) {
-                Ok(0) => {
-                    // EOF - Process died
-                    println!("PaddleOCR: EOF reading stdout, process died.");
-                    let _ = child.kill();
-                    *process_guard = None;
-                    return Err("OCR process closed unexpected (EOF)".to_string());
+            let start = self.next.fetch_add(1, Ordering::Relaxed) % workers.len();
+            let (worker, load) = (0..workers.len())
+                .map(|i| &workers[(start + i) % workers.len()])
+                .map(|w| (w, w.in_flight.load(Ordering::Acquire)))
+                .min_by_key(|(_, load)| *load)
+                .unwrap();
+            if worker.in_flight.compare_exchange(load, load + 1, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+                return Ok(InFlight(Arc::clone(worker)));
+            }
+        }
+    }
+
+    fn shutdown(&self) {
+        for worker in self.workers.read().unwrap().iter() {
+            worker.shutdown();
+        }
+    }
+
+    /// Applies the config's pool size and idle timeout, starting the idle
+    /// reaper on first use. Does nothing while those settings are unchanged.
+    fn configure(&'static self, config: &AppConfig) {
+        let max_workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let wanted = (config.paddle_workers.clamp(1, max_workers), config.paddle_idle_timeout_secs);
+        {
+            // Held until the pool matches, so a concurrent caller can't see the
+            // new settings applied and go on to `pick` from an empty pool
+            let mut applied = self.applied.lock().unwrap();
+            if *applied == Some(wanted) {
+                return;
+            }
+            self.resize(wanted.0);
+            self.idle_timeout_secs.store(wanted.1, Ordering::Relaxed);
+            *applied = Some(wanted);
+        }
+
+        self.reaper.call_once(|| {
+            std::thread::spawn(move || loop {
+                std::thread::sleep(Duration::from_secs(15));
+                let secs = self.idle_timeout_secs.load(Ordering::Relaxed);
+                if secs == 0 {
+                    continue;
                 }
-                Ok(_) => {
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() { continue; }
-                    
-                    println!("Paddle RAW: {}", trimmed);
-                    
-                    match serde_json::from_str::<serde_json::Value>(&line) {
-                        Ok(json_val) => {
-                            let code = json_val["code"].as_i64().unwrap_or(0);
-                            if code == 100 {
-                                // Success: data is array of objects
-                                if let Ok(result) = serde_json::from_value::<PaddleResult>(json_val) {
-                                     if let Some(data) = result.data {
-                                        let full_text = data.into_iter()
-                                            .map(|d| d.text)
-                                            .collect::<Vec<String>>()
-                                            .join("\n");
-                                        return Ok(full_text);
-                                    }
-                                    return Ok("".to_string());
-                                }
-                            } else {
-                                 // Error/No text: data is likely a string
-                                 let msg = json_val["data"].as_str().unwrap_or("Unknown error");
-                                 // We don't need to kill the process for logical errors (like no text found)
-                                 // But for now, returning Err is fine.
-                                 // Wait, if it's just "No text found", maybe we should return Ok("")?
-                                 if code == 101 {
-                                     return Ok("".to_string());
-                                 }
-                                 return Err(format!("PaddleOCR Error {}: {}", code, msg));
-                            }
-                            // Fallback if parsing fails inside
-                            return Err(format!("Failed to parse PaddleOCR response: {}", line));
-                        },
-                        Err(_) => {
-                             // Likely the welcome message or debug info. Ignore and keep waiting for JSON.
-                             println!("PaddleOCR: Ignored non-JSON output: {}", trimmed);
-                             continue;
-                        }
-                    }
+                let workers = self.workers.read().unwrap().clone();
+                for worker in workers {
+                    worker.unload_if_idle(Duration::from_secs(secs));
                 }
-                Err(e) => {
-                    println!("PaddleOCR: Read error ({}), killing.", e);
-                     let _ = child.kill();
-                     *process_guard = None;
-                     return Err(format!("Failed to read from OCR process: {}", e));
+            });
+        });
+    }
+}
+
+static POOL: Lazy<PaddlePool> = Lazy::new(|| PaddlePool {
+    workers: RwLock::new(Vec::new()),
+    next: AtomicUsize::new(0),
+    idle_timeout_secs: AtomicU64::new(0),
+    reaper: Once::new(),
+    applied: Mutex::new(None),
+});
+
+pub struct PaddleBackend;
+
+impl OcrBackend for PaddleBackend {
+    fn name(&self) -> &'static str {
+        "paddle"
+    }
+
+    fn capabilities(&self) -> OcrCapabilities {
+        OcrCapabilities {
+            bounding_boxes: true,
+            confidence: true,
+            language_selection: false,
+            persistent_process: true,
+        }
+    }
+
+    fn supported_languages(&self, _config: &AppConfig) -> Vec<String> {
+        // The bundled default model set
+        vec!["zh-Hans".to_string(), "en".to_string()]
+    }
+
+    fn health(&self, config: &AppConfig) -> BackendHealth {
+        match PaddleSettings::from_config(config).validate(config) {
+            Ok(()) => BackendHealth::Ready,
+            Err(e) => BackendHealth::Unavailable(e),
+        }
+    }
+
+    fn warm_up(&self, config: &AppConfig) -> Result<(), String> {
+        warm_up(config)
+    }
+
+    fn shutdown(&self) {
+        shutdown();
+    }
+
+    fn recognize(&self, image: &DynamicImage, config: &AppConfig) -> Result<OcrDocument, String> {
+        let mut png = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).map_err(|e| e.to_string())?;
+
+        let data = run_paddle_ocr(&png, config)?;
+
+        let mut doc = OcrDocument::new(self.name(), image.width(), image.height());
+        doc.lines = data.into_iter().map(PaddleData::into_line).collect();
+        Ok(doc)
+    }
+}
+
+impl PaddleData {
+    fn into_line(self) -> OcrLine {
+        let polygon = self.box_points
+            .iter()
+            .filter(|p| p.len() >= 2)
+            .map(|p| Point { x: p[0] as f32, y: p[1] as f32 })
+            .collect();
+        OcrLine {
+            text: self.text,
+            words: Vec::new(),
+            polygon,
+            confidence: Some(self.score as f32),
+        }
+    }
+}
+
+impl PaddleProcess {
+    fn spawn(settings: &PaddleSettings) -> Result<Self, String> {
+        let engine_dir = settings.engine_path.parent().ok_or("Invalid engine path")?;
+
+        println!("PaddleOCR: Spawning new process: {} {:?}", settings.engine_path.display(), settings.args);
+        let mut command = Command::new(&settings.engine_path);
+        command
+            .args(&settings.args)
+            .current_dir(engine_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        #[cfg(target_os = "windows")]
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+        // The Linux release keeps its shared libraries (Paddle inference, OpenCV)
+        // in a `lib` directory next to `bin`.
+        #[cfg(not(target_os = "windows"))]
+        {
+            let mut lib_dirs = vec![engine_dir.to_path_buf()];
+            if let Some(root) = engine_dir.parent() {
+                lib_dirs.push(root.join("lib"));
+            }
+            lib_dirs.extend(std::env::var_os("LD_LIBRARY_PATH").map(|p| std::env::split_paths(&p).collect::<Vec<_>>()).unwrap_or_default());
+            if let Ok(joined) = std::env::join_paths(lib_dirs) {
+                command.env("LD_LIBRARY_PATH", joined);
+            }
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to spawn PaddleOCR: {}", e))?;
+
+        let stdin = child.stdin.take().ok_or("Child process stdin not captured")?;
+        let stdout = child.stdout.take().ok_or("Child process stdout not captured")?;
+        let stderr = child.stderr.take().ok_or("Child process stderr not captured")?;
+
+        let pid = child.id();
+        let (tx, output) = mpsc::channel();
+        std::thread::spawn(move || read_stdout(pid, stdout, tx));
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let tail = Arc::clone(&stderr_tail);
+        std::thread::spawn(move || drain_stderr(pid, stderr, tail));
+
+        Ok(Self {
+            child,
+            stdin,
+            output,
+            stderr_tail,
+            next_seq: 0,
+            settings: settings.clone(),
+            base64_supported: true,
+            last_used: Instant::now(),
+        })
+    }
+
+    /// Spawns the engine and waits until its models are loaded, so the first
+    /// request gets the normal timeout rather than paying for the load.
+    fn spawn_ready(worker_id: usize, settings: &PaddleSettings, timeout: Duration) -> Result<Self, String> {
+        let started = Instant::now();
+        let mut process = Self::spawn(settings)?;
+        if let Err(e) = process.wait_ready(timeout) {
+            process.kill();
+            return Err(e.to_string());
+        }
+        println!("PaddleOCR: Worker {} ready in {:?}.", worker_id, started.elapsed());
+        Ok(process)
+    }
+
+    fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+    /// Asks the engine to exit and closes its stdin, which also ends its read
+    /// loop. Falls back to killing it if it is still running shortly after.
+    fn shutdown(mut self) {
+        let _ = self.stdin.write_all(b"exit\n").and_then(|_| self.stdin.flush());
+        drop(self.stdin);
+
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        println!("PaddleOCR: Engine did not exit in time, killing it.");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+    /// Waits for the init banner printed once the models are loaded, or for
+    /// the first JSON line. A live process that shows neither is assumed ready
+    /// once `timeout` is up, and remembered as a build without a banner.
+    fn wait_ready(&mut self, timeout: Duration) -> Result<(), RequestError> {
+        let no_banner = NO_BANNER.lock().unwrap().as_ref() == Some(&self.settings);
+        let wait = if no_banner { timeout.min(NO_BANNER_GRACE) } else { timeout };
+        match recv_ready(&self.output, wait, &mut self.next_seq) {
+            Ok(()) => Ok(()),
+            Err(mpsc::RecvTimeoutError::Timeout) if matches!(self.child.try_wait(), Ok(None)) => {
+                println!("PaddleOCR: No init banner after {:?}, assuming the engine is ready.", wait);
+                *NO_BANNER.lock().unwrap() = Some(self.settings.clone());
+                Ok(())
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(RequestError::Timeout(wait)),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(RequestError::Fatal(self.with_stderr("OCR process exited during startup (EOF)".to_string())))
+            }
+        }
+    }
+
+    fn recognize_png(&mut self, png: &[u8], transport: ImageTransport, timeout: Duration) -> Result<Vec<PaddleData>, RequestError> {
+        use base64::{Engine as _, engine::general_purpose};
+
+        if transport == ImageTransport::Base64 && self.base64_supported {
+            println!("Paddle PAYLOAD: image_base64 ({} bytes PNG)", png.len());
+            let payload = serde_json::json!({ "image_base64": general_purpose::STANDARD.encode(png) });
+            match self.request(&payload, timeout) {
+                Err(RequestError::Engine(e)) => {
+                    // Older engines don't know `image_base64` and answer with an error.
+                    // Retry from a file; if that works, stick to files for this process.
+                    println!("PaddleOCR: base64 request rejected ({}), retrying with a temp file.", e);
+                    let result = self.recognize_via_file(png, timeout);
+                    if result.is_ok() {
+                        self.base64_supported = false;
+                    }
+                    return result;
                 }
+                other => return other,
+            }
+        }
+
+        self.recognize_via_file(png, timeout)
+    }
+
+    fn recognize_via_file(&mut self, png: &[u8], timeout: Duration) -> Result<Vec<PaddleData>, RequestError> {
+        let temp = TempImage::write(png).map_err(RequestError::Fatal)?;
+        let payload = serde_json::json!({ "image_path": temp.0.to_string_lossy() });
+        println!("Paddle PAYLOAD: {}", payload);
+        self.request(&payload, timeout)
+    }
+
+    /// Sends one request and waits at most `timeout` for the response that
+    /// belongs to it. Responses are matched by position: the engine answers
+    /// requests strictly in order, one JSON line each.
+    fn request(&mut self, payload: &serde_json::Value, timeout: Duration) -> Result<Vec<PaddleData>, RequestError> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.stdin.write_all(payload.to_string().as_bytes())
+            .and_then(|_| self.stdin.write_all(b"\n"))
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| RequestError::Fatal(self.with_stderr(format!("Failed to write to OCR process: {}", e))))?;
+
+        let value = recv_response(&self.output, seq, timeout).map_err(|e| match e {
+            mpsc::RecvTimeoutError::Timeout => RequestError::Timeout(timeout),
+            mpsc::RecvTimeoutError::Disconnected => RequestError::Fatal(self.with_stderr("OCR process closed unexpected (EOF)".to_string())),
+        })?;
+
+        let code = value["code"].as_i64().unwrap_or(0);
+        match code {
+            // Success: data is array of objects
+            100 => serde_json::from_value::<PaddleResult>(value.clone())
+                .map(|result| result.data.unwrap_or_default())
+                .map_err(|_| RequestError::Engine(format!("Failed to parse PaddleOCR response: {}", value))),
+            // No text found is not an error
+            101 => Ok(Vec::new()),
+            // Error: data is the engine's message
+            _ => {
+                let msg = value["data"].as_str().or(value["message"].as_str()).unwrap_or("").trim();
+                let msg = if msg.is_empty() { self.with_stderr("Unknown error".to_string()) } else { msg.to_string() };
+                Err(RequestError::Engine(format!("PaddleOCR Error {}: {}", code, msg)))
+            }
+        }
+    }
+
+    /// Appends the engine's recent stderr, which usually says why it died.
+    fn with_stderr(&self, error: String) -> String {
+        let tail = self.stderr_tail.lock().map(|t| t.iter().cloned().collect::<Vec<_>>().join(" | ")).unwrap_or_default();
+        if tail.is_empty() {
+            error
+        } else {
+            format!("{} (stderr: {})", error, tail)
+        }
+    }
+}
+
+/// Waits for `Output::Ready` or the first response, whichever comes first;
+/// an engine that answers is up even if it never printed the banner. The
+/// response used up a sequence number in the reader, so `next_seq` is moved
+/// past it; otherwise the first request would wait for it forever.
+fn recv_ready(output: &mpsc::Receiver<Output>, timeout: Duration, next_seq: &mut u64) -> Result<(), mpsc::RecvTimeoutError> {
+    match output.recv_timeout(timeout)? {
+        Output::Ready => {}
+        Output::Response { seq, .. } => {
+            println!("PaddleOCR: Response #{} arrived before the init banner, treating the engine as ready", seq);
+            *next_seq = seq + 1;
+        }
+    }
+    Ok(())
+}
+
+/// Waits for the `seq`-th response, skipping older ones left over from
+/// requests that timed out.
+fn recv_response(output: &mpsc::Receiver<Output>, seq: u64, timeout: Duration) -> Result<serde_json::Value, mpsc::RecvTimeoutError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match output.recv_timeout(deadline.saturating_duration_since(Instant::now()))? {
+            Output::Response { seq: got, value } if got == seq => return Ok(value),
+            Output::Response { seq: got, .. } => {
+                println!("PaddleOCR: Discarding stale response #{} (waiting for #{})", got, seq);
+            }
+            Output::Ready => {}
+        }
+    }
+}
+
+/// Frames stdout into JSON responses until the process closes it. Dropping
+/// `tx` on EOF tells the request side that the engine is gone.
+fn read_stdout(pid: u32, stdout: impl Read, tx: mpsc::Sender<Output>) {
+    let mut seq = 0;
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else { break };
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        // The engine prints its version and init messages before any JSON
+        let output = match serde_json::from_str::<serde_json::Value>(trimmed) {
+            Ok(value) if value.is_object() => {
+                println!("Paddle RAW [{}]: {}", pid, trimmed);
+                seq += 1;
+                Output::Response { seq: seq - 1, value }
+            }
+            _ if trimmed.contains(INIT_BANNER) => {
+                println!("Paddle INIT [{}]: {}", pid, trimmed);
+                Output::Ready
+            }
+            _ => {
+                println!("PaddleOCR [{}]: Ignored non-JSON output: {}", pid, trimmed);
+                continue;
+            }
+        };
+        if tx.send(output).is_err() {
+            break;
+        }
+    }
+}
+
+/// Keeps stderr flowing so a chatty engine can't block on a full pipe, and
+/// remembers the last few lines for error messages.
+fn drain_stderr(pid: u32, stderr: ChildStderr, tail: Arc<Mutex<VecDeque<String>>>) {
+    for line in BufReader::new(stderr).lines() {
+        let Ok(line) = line else { break };
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        println!("Paddle STDERR [{}]: {}", pid, trimmed);
+        if let Ok(mut tail) = tail.lock() {
+            if tail.len() == STDERR_TAIL_LINES {
+                tail.pop_front();
             }
+            tail.push_back(trimmed.to_string());
+        }
+    }
+}
+
+/// The Windows build finds its `models` folder relative to the working dir,
+/// which we set to the exe's folder. The Linux build ships the binary in `bin/`
+/// with `models/` beside it, so point it there explicitly.
+fn default_models_dir(engine_path: &std::path::Path) -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        return None;
+    }
+    let dir = engine_path.parent()?.parent()?.join("models");
+    dir.is_dir().then_some(dir)
+}
+
+pub fn run_paddle_ocr(png: &[u8], config: &AppConfig) -> Result<Vec<PaddleData>, String> {
+    let settings = &PaddleSettings::from_config(config);
+    let transport = ImageTransport::from_config(config);
+    let timeout = Duration::from_millis(config.paddle_timeout_ms);
+
+    POOL.configure(config);
+
+    let claim = POOL.pick()?;
+    run_on_worker(&claim.0, png, settings, transport, timeout)
+}
+
+fn run_on_worker(worker: &Worker, png: &[u8], settings: &PaddleSettings, transport: ImageTransport, timeout: Duration) -> Result<Vec<PaddleData>, String> {
+    // A worker's process handles one request at a time
+    let mut process_guard = worker.process.lock().map_err(|e| format!("Mutex poisoned: {}", e))?;
+
+    // Engine settings changed since the process was started: restart it. The
+    // old process is idle here, so let it exit cleanly like an idle unload.
+    if process_guard.as_ref().is_some_and(|p| p.settings != *settings) {
+        println!("PaddleOCR: Settings changed, restarting worker {}.", worker.id);
+        if let Some(old) = process_guard.take() {
+            old.shutdown();
+        }
+    }
+
+    // Check if process exists and is alive; if not, spawn it. This happens
+    // after a crash, an idle unload or a settings change, and the model load
+    // must not eat into the request's own timeout.
+    if process_guard.is_none() {
+        println!("PaddleOCR: Starting worker {}.", worker.id);
+        *process_guard = Some(PaddleProcess::spawn_ready(worker.id, settings, startup_timeout(timeout))?);
+    }
+
+    let process = process_guard.as_mut().unwrap();
+    process.last_used = Instant::now();
+    let result = process.recognize_png(png, transport, timeout);
+    process.last_used = Instant::now();
+    match result {
+        Ok(data) => Ok(data),
+        Err(RequestError::Engine(e)) => Err(e),
+        Err(e) => {
+            println!("PaddleOCR: Worker {}: {}, killing process.", worker.id, e);
+            process.kill();
+            *process_guard = None; // Reset so next call respawns
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Model loading takes longer than a request, so startup gets a generous
+/// deadline of its own.
+fn startup_timeout(request_timeout: Duration) -> Duration {
+    request_timeout.max(Duration::from_secs(30))
+}
+
+/// Starts every worker and waits until their models are loaded, so the first
+/// capture doesn't pay the startup cost.
+pub fn warm_up(config: &AppConfig) -> Result<(), String> {
+    let settings = PaddleSettings::from_config(config);
+    settings.validate(config)?;
+    POOL.configure(config);
+
+    let timeout = startup_timeout(Duration::from_millis(config.paddle_timeout_ms));
+    let workers = POOL.workers.read().unwrap().clone();
+    let handles: Vec<_> = workers
+        .into_iter()
+        .map(|worker| {
+            let settings = settings.clone();
+            std::thread::spawn(move || worker.warm_up(&settings, timeout))
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().map_err(|_| "PaddleOCR warm-up thread panicked".to_string())??;
+    }
+    Ok(())
+}
+
+/// Stops every worker process. Called on quit and app exit.
+pub fn shutdown() {
+    POOL.shutdown();
+}
+
+/// Spawns a throwaway engine with the given settings and runs it on a blank
+/// image. Used by the settings page before the user commits to a config.
+pub fn test_engine(config: &AppConfig) -> Result<(), String> {
+    let settings = PaddleSettings::from_config(config);
+    settings.validate(config)?;
+
+    let mut probe = Vec::new();
+    DynamicImage::new_luma8(64, 32).write_to(&mut std::io::Cursor::new(&mut probe), image::ImageFormat::Png).map_err(|e| e.to_string())?;
+
+    // Wait for the models first so a slow load isn't mistaken for a broken engine
+    let timeout = Duration::from_millis(config.paddle_timeout_ms);
+    let mut process = PaddleProcess::spawn_ready(0, &settings, startup_timeout(timeout))?;
+    let result = process.recognize_png(&probe, ImageTransport::from_config(config), timeout);
+    process.kill();
+
+    result.map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader_over(stdout: &str) -> mpsc::Receiver<Output> {
+        let (tx, output) = mpsc::channel();
+        read_stdout(0, std::io::Cursor::new(stdout.to_string().into_bytes()), tx);
+        output
+    }
+
+    fn empty_pool() -> PaddlePool {
+        PaddlePool {
+            workers: RwLock::new(Vec::new()),
+            next: AtomicUsize::new(0),
+            idle_timeout_secs: AtomicU64::new(0),
+            reaper: Once::new(),
+            applied: Mutex::new(None),
         }
-    } else {
-        *process_guard = None;
-        return Err("Child process stdout not captured".to_string());
+    }
+
+    #[test]
+    fn picking_from_an_empty_pool_is_an_error() {
+        assert!(empty_pool().pick().is_err());
+    }
+
+    #[test]
+    fn picks_spread_over_idle_workers() {
+        let pool = empty_pool();
+        pool.resize(2);
+        let first = pool.pick().unwrap();
+        let second = pool.pick().unwrap();
+        assert_ne!(first.0.id, second.0.id);
+        drop(first);
+        assert_eq!(pool.workers.read().unwrap().iter().map(|w| w.in_flight.load(Ordering::Acquire)).sum::<usize>(), 1);
+    }
+
+    #[test]
+    fn responses_before_the_banner_do_not_shift_the_next_request() {
+        let output = reader_over(concat!(
+            "PaddleOCR-json v1.4.0\n",
+            "{\"code\":101,\"data\":\"\"}\n",
+            "OCR init completed.\n",
+            "\n",
+            "{\"code\":100,\"data\":[{\"text\":\"hi\",\"box_points\":[],\"score\":0.9}]}\n",
+        ));
+
+        let mut next_seq = 0;
+        recv_ready(&output, Duration::from_secs(1), &mut next_seq).unwrap();
+        assert_eq!(next_seq, 1);
+
+        let value = recv_response(&output, next_seq, Duration::from_secs(1)).unwrap();
+        assert_eq!(value["code"], 100);
+        assert!(matches!(recv_response(&output, 2, Duration::from_secs(1)), Err(mpsc::RecvTimeoutError::Disconnected)));
+    }
+
+    #[test]
+    fn first_response_counts_as_ready_without_a_banner() {
+        let output = reader_over("PaddleOCR-json v1.4.0\n{\"code\":101,\"seq\":0}\n{\"code\":101,\"seq\":1}\n");
+
+        let mut next_seq = 0;
+        recv_ready(&output, Duration::from_secs(1), &mut next_seq).unwrap();
+        assert_eq!(next_seq, 1);
+        assert_eq!(recv_response(&output, next_seq, Duration::from_secs(1)).unwrap()["seq"], 1);
+    }
+
+    #[test]
+    fn stale_responses_are_skipped() {
+        let output = reader_over("OCR init completed.\n{\"code\":101,\"seq\":0}\nnot json\n{\"code\":101,\"seq\":1}\n");
+
+        let mut next_seq = 0;
+        recv_ready(&output, Duration::from_secs(1), &mut next_seq).unwrap();
+        assert_eq!(next_seq, 0);
+
+        // The request for #0 timed out; the one for #1 must not get its answer
+        let value = recv_response(&output, 1, Duration::from_secs(1)).unwrap();
+        assert_eq!(value["seq"], 1);
     }
 }
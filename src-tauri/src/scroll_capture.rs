@@ -0,0 +1,382 @@
+use image::RgbaImage;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::ocr_core::{self, CaptureGeometry, MonitorInfo};
+
+/// Pause between frames. Short enough to keep up with wheel scrolling, so
+/// consecutive frames still overlap.
+const FRAME_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Stitching stops on its own at this height to bound memory use.
+const MAX_HEIGHT: u32 = 30_000;
+
+/// Rows are compared as this many column averages; enough to tell lines of
+/// text apart, cheap enough to try every shift.
+const PROFILE_BINS: u32 = 64;
+
+/// Mean luma difference per bin below which two rows count as the same.
+const MATCH_THRESHOLD: f32 = 2.5;
+
+/// How two consecutive frames of a scrolling region line up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overlap {
+    /// Rows the content moved up between the frames; 0 when it did not move.
+    pub shift: u32,
+    /// Rows at the top that stayed put (a sticky header).
+    pub header: u32,
+    /// Rows at the bottom that stayed put (a sticky footer or input box).
+    pub footer: u32,
+}
+
+/// Finds how far the content scrolled from `prev` to `next`, ignoring sticky
+/// headers and footers. Returns `None` when the frames differ in size or no
+/// shift makes them agree, e.g. after scrolling more than a frame at once or
+/// scrolling back up.
+pub fn find_overlap(prev: &RgbaImage, next: &RgbaImage) -> Option<Overlap> {
+    if prev.dimensions() != next.dimensions() || prev.height() == 0 {
+        return None;
+    }
+    let height = prev.height() as usize;
+    let row_bytes = prev.width() as usize * 4;
+    let (prev_raw, next_raw) = (prev.as_raw(), next.as_raw());
+    let same_row = |y: usize| prev_raw[y * row_bytes..(y + 1) * row_bytes] == next_raw[y * row_bytes..(y + 1) * row_bytes];
+
+    let header = (0..height).take_while(|&y| same_row(y)).count();
+    if header == height {
+        return Some(Overlap { shift: 0, header: 0, footer: 0 });
+    }
+    // Blank margins look static too; cap both so enough rows are left to match
+    let header = header.min(height / 3);
+    let footer = (0..height).rev().take_while(|&y| same_row(y)).count().min(height / 3);
+
+    let prev_profile = row_profile(prev);
+    let next_profile = row_profile(next);
+    let bins = prev_profile.len() / height;
+    let (band_start, band_end) = (header, height - footer);
+    let band = band_end - band_start;
+    // At least an eighth of the band must overlap to trust a match
+    let min_overlap = (band / 8).max(8);
+    if band <= min_overlap {
+        return None;
+    }
+
+    let mut best: Option<(usize, f32)> = None;
+    for shift in 0..=band - min_overlap {
+        let rows = band - shift;
+        let limit = best.map_or(MATCH_THRESHOLD, |(_, cost)| cost.min(MATCH_THRESHOLD)) * (rows * bins) as f32;
+        let mut total = 0.0;
+        for y in band_start..band_end - shift {
+            let a = &prev_profile[(y + shift) * bins..(y + shift + 1) * bins];
+            let b = &next_profile[y * bins..(y + 1) * bins];
+            total += a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum::<f32>();
+            if total >= limit {
+                break;
+            }
+        }
+        if total < limit {
+            best = Some((shift, total / (rows * bins) as f32));
+        }
+    }
+
+    best.map(|(shift, _)| Overlap { shift: shift as u32, header: header as u32, footer: footer as u32 })
+}
+
+/// Per row, the mean luma of `PROFILE_BINS` equal column slices.
+fn row_profile(image: &RgbaImage) -> Vec<f32> {
+    let (width, height) = image.dimensions();
+    let bins = PROFILE_BINS.min(width).max(1);
+    let mut profile = vec![0.0f32; (height * bins) as usize];
+    let mut counts = vec![0u32; bins as usize];
+    for x in 0..width {
+        counts[(x * bins / width) as usize] += 1;
+    }
+    for (x, y, p) in image.enumerate_pixels() {
+        let luma = 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32;
+        let bin = x * bins / width;
+        profile[(y * bins + bin) as usize] += luma / counts[bin as usize] as f32;
+    }
+    profile
+}
+
+/// What `ScrollStitcher::push` did with a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StitchResult {
+    /// The content scrolled; this many new rows were appended.
+    Added(u32),
+    /// Nothing scrolled.
+    Unchanged,
+    /// The frame did not line up with the previous one and was dropped.
+    NoMatch,
+}
+
+/// Builds one tall image from frames of a region whose content scrolls down.
+/// Sticky footers stay at the bottom of the result instead of repeating.
+pub struct ScrollStitcher {
+    canvas: RgbaImage,
+    last: RgbaImage,
+}
+
+impl ScrollStitcher {
+    pub fn new(first: RgbaImage) -> Self {
+        Self { canvas: first.clone(), last: first }
+    }
+
+    pub fn height(&self) -> u32 {
+        self.canvas.height()
+    }
+
+    pub fn push(&mut self, frame: RgbaImage) -> StitchResult {
+        let Some(overlap) = find_overlap(&self.last, &frame) else {
+            return StitchResult::NoMatch;
+        };
+        if overlap.shift == 0 {
+            return StitchResult::Unchanged;
+        }
+
+        // Drop the previous footer, then append the new rows and the footer
+        // again: the last `footer + shift` rows of the frame
+        let (width, height) = frame.dimensions();
+        let keep = self.canvas.height() - overlap.footer;
+        let tail = overlap.footer + overlap.shift;
+        let row_bytes = width as usize * 4;
+        let mut raw = std::mem::take(&mut self.canvas).into_raw();
+        raw.truncate(keep as usize * row_bytes);
+        raw.extend_from_slice(&frame.as_raw()[(height - tail) as usize * row_bytes..]);
+        self.canvas = RgbaImage::from_raw(width, keep + tail, raw).expect("rows are whole");
+        self.last = frame;
+        StitchResult::Added(overlap.shift)
+    }
+
+    pub fn finish(self) -> RgbaImage {
+        self.canvas
+    }
+}
+
+/// Reported to the frontend after every frame.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrollProgress {
+    /// Height of the stitched image so far, in physical pixels.
+    pub height: u32,
+    /// The last frame could not be lined up; scrolling back a little
+    /// usually recovers.
+    pub lost: bool,
+    /// Stitching stopped at `MAX_HEIGHT`.
+    pub full: bool,
+}
+
+struct ScrollSession {
+    stop: Arc<AtomicBool>,
+    worker: JoinHandle<Result<RgbaImage, String>>,
+    monitor: MonitorInfo,
+    origin: (i32, i32),
+}
+
+static SESSION: Lazy<Mutex<Option<ScrollSession>>> = Lazy::new(|| Mutex::new(None));
+
+/// Starts capturing the physical desktop rectangle (`x`, `y`, `width`,
+/// `height`) on `monitor` every `FRAME_INTERVAL` and stitching the frames,
+/// until `stop` is called. Any running session is discarded.
+pub fn start(
+    monitor: MonitorInfo,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    on_progress: impl Fn(ScrollProgress) + Send + 'static,
+) -> Result<(), String> {
+    // Frames come from one monitor; a region spilling over is cut at its edge
+    let (x0, y0) = (x.max(monitor.x), y.max(monitor.y));
+    let x1 = (x + width as i32).min(monitor.x + monitor.width as i32);
+    let y1 = (y + height as i32).min(monitor.y + monitor.height as i32);
+    if x1 <= x0 || y1 <= y0 {
+        return Err("Scrolling capture region is outside the monitor".to_string());
+    }
+    let (x, y, width, height) = (x0, y0, (x1 - x0) as u32, (y1 - y0) as u32);
+
+    let first = ocr_core::capture_region(&monitor, x, y, width, height)?;
+    println!("scroll_capture: started at {:?} on '{}'", (x, y, width, height), monitor.name);
+
+    let monitor_info = monitor.clone();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = stop.clone();
+    let worker = std::thread::spawn(move || {
+        let mut stitcher = ScrollStitcher::new(first);
+        let mut frames = 1;
+        while !stop_flag.load(Ordering::Relaxed) {
+            std::thread::sleep(FRAME_INTERVAL);
+            let frame = match ocr_core::capture_region(&monitor, x, y, width, height) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    println!("scroll_capture: Frame capture failed: {}", e);
+                    continue;
+                }
+            };
+            let result = stitcher.push(frame);
+            match result {
+                StitchResult::Unchanged => continue,
+                StitchResult::Added(_) => frames += 1,
+                // Still reported so the overlay can say the frame was lost
+                StitchResult::NoMatch => {}
+            }
+            let full = stitcher.height() >= MAX_HEIGHT;
+            on_progress(ScrollProgress { height: stitcher.height(), lost: result == StitchResult::NoMatch, full });
+            if full {
+                println!("scroll_capture: Reached {} px, stopping", MAX_HEIGHT);
+                break;
+            }
+        }
+        println!("scroll_capture: {} frame(s), stitched {}x{}", frames, width, stitcher.height());
+        Ok(stitcher.finish())
+    });
+
+    let previous = SESSION.lock().unwrap().replace(ScrollSession { stop, worker, monitor: monitor_info, origin: (x, y) });
+    if let Some(previous) = previous {
+        previous.stop.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// Stops the running session and caches the stitched image in place of the
+/// screenshot, anchored at the region's top-left corner. Returns its size.
+pub fn stop() -> Result<(u32, u32), String> {
+    let session = SESSION.lock().unwrap().take().ok_or("No scrolling capture in progress")?;
+    session.stop.store(true, Ordering::Relaxed);
+    let image = session.worker.join().map_err(|_| "Scrolling capture thread panicked".to_string())??;
+
+    let (width, height) = image.dimensions();
+    let geometry = CaptureGeometry { x: session.origin.0, y: session.origin.1, width, height, monitors: vec![session.monitor] };
+    ocr_core::set_cached_capture(image::DynamicImage::ImageRgba8(image), geometry);
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{imageops, Rgba};
+
+    const INK: Rgba<u8> = Rgba([20, 20, 20, 255]);
+    const HEADER: Rgba<u8> = Rgba([40, 90, 200, 255]);
+    const FOOTER: Rgba<u8> = Rgba([200, 200, 200, 255]);
+
+    /// A tall white page with pseudo-random lines of "text" starting at
+    /// `top`, so no two stretches of it look alike.
+    fn page(width: u32, height: u32, top: u32, seed: u32) -> RgbaImage {
+        let mut image = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+        let mut state = seed;
+        let mut y = top;
+        while y + 12 < height {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            let len = 40 + (state >> 16) % (width - 60);
+            for row in y..y + 10 {
+                for x in 10..10 + len {
+                    if (x / 3 + row + (state >> 8)) % 4 < 3 {
+                        image.put_pixel(x, row, INK);
+                    }
+                }
+            }
+            y += 14 + (state >> 20) % 10;
+        }
+        image
+    }
+
+    /// What a `height` px tall window shows with the page scrolled to
+    /// `offset`, under a sticky header and over a sticky footer.
+    fn view(page: &RgbaImage, offset: u32, height: u32, header: u32, footer: u32) -> RgbaImage {
+        let width = page.width();
+        let mut frame = imageops::crop_imm(page, 0, offset, width, height).to_image();
+        for y in 0..header {
+            for x in 0..width {
+                frame.put_pixel(x, y, HEADER);
+            }
+        }
+        for y in height - footer..height {
+            for x in 0..width {
+                frame.put_pixel(x, y, if y == height - footer / 2 && x < width / 3 { INK } else { FOOTER });
+            }
+        }
+        frame
+    }
+
+    fn stitch(page: &RgbaImage, offsets: &[u32], height: u32, header: u32, footer: u32) -> (Vec<StitchResult>, RgbaImage) {
+        let mut stitcher = ScrollStitcher::new(view(page, offsets[0], height, header, footer));
+        let results = offsets[1..].iter().map(|&offset| stitcher.push(view(page, offset, height, header, footer))).collect();
+        (results, stitcher.finish())
+    }
+
+    #[test]
+    fn plain_scroll_reproduces_the_page() {
+        let page = page(400, 3000, 5, 7);
+        let (results, stitched) = stitch(&page, &[0, 120, 300, 300, 450, 800, 1100, 1500], 500, 0, 0);
+        assert_eq!(results[2], StitchResult::Unchanged);
+        assert_eq!(results[3], StitchResult::Added(150));
+        assert_eq!(stitched.as_raw(), imageops::crop_imm(&page, 0, 0, 400, 2000).to_image().as_raw());
+    }
+
+    #[test]
+    fn sticky_header_and_footer_appear_once() {
+        let page = page(400, 3000, 5, 11);
+        let (results, stitched) = stitch(&page, &[0, 100, 250, 600, 700], 500, 40, 30);
+        assert_eq!(results, [100, 150, 350, 100].map(StitchResult::Added));
+        // The header stays on top and the footer moves to the very bottom, as
+        // if the whole scrolled range had been one tall window
+        assert_eq!(stitched.as_raw(), view(&page, 0, 1200, 40, 30).as_raw());
+    }
+
+    #[test]
+    fn non_overlapping_frames_do_not_match() {
+        let page = page(400, 3000, 5, 3);
+        let mut stitcher = ScrollStitcher::new(view(&page, 1000, 500, 0, 0));
+        // Scrolled past a whole frame, then back up
+        assert_eq!(stitcher.push(view(&page, 1600, 500, 0, 0)), StitchResult::NoMatch);
+        assert_eq!(stitcher.push(view(&page, 900, 500, 0, 0)), StitchResult::NoMatch);
+        assert_eq!(stitcher.height(), 500);
+        // Dropped frames don't replace the reference, so scrolling back recovers
+        assert_eq!(stitcher.push(view(&page, 1200, 500, 0, 0)), StitchResult::Added(200));
+        assert_eq!(stitcher.finish().as_raw(), imageops::crop_imm(&page, 0, 1000, 400, 700).to_image().as_raw());
+    }
+
+    #[test]
+    fn blank_frames() {
+        let blank = RgbaImage::from_pixel(400, 500, Rgba([255, 255, 255, 255]));
+        assert_eq!(find_overlap(&blank, &blank), Some(Overlap { shift: 0, header: 0, footer: 0 }));
+
+        let page = page(400, 3000, 5, 5);
+        let mut stitcher = ScrollStitcher::new(blank.clone());
+        assert_eq!(stitcher.push(blank.clone()), StitchResult::Unchanged);
+        assert_eq!(stitcher.push(view(&page, 0, 500, 0, 0)), StitchResult::NoMatch);
+        assert_eq!(stitcher.height(), 500);
+
+        let mut stitcher = ScrollStitcher::new(view(&page, 0, 500, 0, 0));
+        assert_eq!(stitcher.push(blank), StitchResult::NoMatch);
+        assert_eq!(stitcher.height(), 500);
+    }
+
+    #[test]
+    fn blank_margins_are_capped_as_static_rows() {
+        // The page starts with 400 blank rows, so the top 300 rows of both
+        // frames agree although the content moved
+        let page = page(400, 3000, 400, 9);
+        let overlap = find_overlap(&view(&page, 0, 500, 0, 0), &view(&page, 100, 500, 0, 0)).unwrap();
+        assert_eq!((overlap.shift, overlap.header), (100, 500 / 3));
+    }
+
+    #[test]
+    fn overlap_must_cover_an_eighth_of_the_band() {
+        let page = page(400, 3000, 5, 13);
+        let first = view(&page, 0, 500, 0, 0);
+        // 70 rows left in common is enough, 50 is not (the minimum is 62)
+        assert_eq!(find_overlap(&first, &view(&page, 430, 500, 0, 0)).map(|o| o.shift), Some(430));
+        assert_eq!(find_overlap(&first, &view(&page, 450, 500, 0, 0)), None);
+    }
+
+    #[test]
+    fn frames_of_different_sizes_do_not_match() {
+        let page = page(400, 3000, 5, 1);
+        assert_eq!(find_overlap(&view(&page, 0, 500, 0, 0), &view(&page, 100, 480, 0, 0)), None);
+    }
+}
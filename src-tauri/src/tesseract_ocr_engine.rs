@@ -0,0 +1,312 @@
+use std::io::{Read, Write};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use image::DynamicImage;
+
+use crate::config::{resolve_resource_path, AppConfig};
+use crate::ocr_backend::{BackendHealth, OcrBackend, OcrCapabilities};
+use crate::ocr_document::{rect_polygon, OcrDocument, OcrLine, OcrWord};
+
+/// Drives the `tesseract` CLI. The image goes in over stdin as PNG and the
+/// result comes back as TSV, which carries word boxes and confidences.
+pub struct TesseractBackend;
+
+impl OcrBackend for TesseractBackend {
+    fn name(&self) -> &'static str {
+        "tesseract"
+    }
+
+    fn capabilities(&self) -> OcrCapabilities {
+        OcrCapabilities {
+            bounding_boxes: true,
+            confidence: true,
+            language_selection: true,
+            persistent_process: false,
+        }
+    }
+
+    fn supported_languages(&self, config: &AppConfig) -> Vec<String> {
+        list_installed_languages(config)
+            .map(|langs| langs.iter().map(|l| to_language_tag(l)).collect())
+            .unwrap_or_default()
+    }
+
+    fn health(&self, config: &AppConfig) -> BackendHealth {
+        let installed = match list_installed_languages(config) {
+            Ok(langs) => langs,
+            Err(e) => return BackendHealth::Unavailable(e),
+        };
+        let missing: Vec<&str> = config.tesseract_languages
+            .iter()
+            .map(|l| l.as_str())
+            .filter(|l| !installed.iter().any(|i| i == l))
+            .collect();
+
+        if missing.is_empty() {
+            BackendHealth::Ready
+        } else if missing.len() == config.tesseract_languages.len() {
+            BackendHealth::Unavailable(format!("Language packs not installed: {}", missing.join(", ")))
+        } else {
+            BackendHealth::Degraded(format!("Language packs not installed: {}", missing.join(", ")))
+        }
+    }
+
+    fn recognize(&self, image: &DynamicImage, config: &AppConfig) -> Result<OcrDocument, String> {
+        let mut png = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).map_err(|e| e.to_string())?;
+
+        // Only ask for packs that are actually installed; tesseract aborts on unknown ones
+        let installed = list_installed_languages(config)?;
+        let langs: Vec<&str> = config.tesseract_languages
+            .iter()
+            .map(|l| l.as_str())
+            .filter(|l| installed.iter().any(|i| i == l))
+            .collect();
+        let lang_arg = if langs.is_empty() { "eng".to_string() } else { langs.join("+") };
+
+        println!("tesseract: Recognizing {}x{} with -l {}", image.width(), image.height(), lang_arg);
+        let mut child = tesseract_command(config)
+            .args(["stdin", "stdout", "-l", &lang_arg, "--psm", "3", "tsv"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to spawn tesseract: {}", e))?;
+
+        // Feed stdin from a separate thread so a chatty stdout can't deadlock us
+        let mut stdin = child.stdin.take().ok_or("tesseract stdin not captured")?;
+        let writer = std::thread::spawn(move || stdin.write_all(&png));
+
+        let stdout = read_to_end(child.stdout.take().ok_or("tesseract stdout not captured")?);
+        let stderr = read_to_end(child.stderr.take().ok_or("tesseract stderr not captured")?);
+
+        // A stuck tesseract must not hold up the OCR command; killing it closes
+        // its pipes, which ends the reader threads too
+        let timeout = Duration::from_millis(config.tesseract_timeout_ms);
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+                Ok(None) => {
+                    println!("tesseract: No result after {:?}, killing it.", timeout);
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!("tesseract timed out after {:?}", timeout));
+                }
+                Err(e) => return Err(format!("Failed to wait for tesseract: {}", e)),
+            }
+        };
+        let _ = writer.join();
+        let stdout = stdout.join().map_err(|_| "tesseract stdout reader panicked".to_string())?;
+        let stderr = stderr.join().map_err(|_| "tesseract stderr reader panicked".to_string())?;
+
+        if !status.success() {
+            return Err(format!("tesseract exited with {}: {}", status, String::from_utf8_lossy(&stderr).trim()));
+        }
+
+        let mut doc = parse_tsv(&String::from_utf8_lossy(&stdout), image.width(), image.height());
+        doc.language = langs.first().map(|l| to_language_tag(l));
+        Ok(doc)
+    }
+}
+
+/// Drains a pipe on its own thread so neither stdout nor stderr can fill up
+/// and block the process.
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+fn tesseract_command(config: &AppConfig) -> Command {
+    let program = if config.tesseract_path.trim().is_empty() {
+        "tesseract".into()
+    } else if config.tesseract_path.contains(['/', '\\']) {
+        resolve_resource_path(&config.tesseract_path)
+    } else {
+        // Bare program name: let the OS search PATH
+        config.tesseract_path.trim().into()
+    };
+
+    let mut command = Command::new(program);
+    hide_console_window(&mut command);
+    command
+}
+
+fn hide_console_window(_command: &mut Command) {
+    #[cfg(target_os = "windows")]
+    _command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+}
+
+/// Installed packs by `tesseract_path` and `TESSDATA_PREFIX`. `health` and
+/// every `recognize` need them, and spawning `--list-langs` each time is slow
+/// on Windows.
+static INSTALLED_LANGUAGES: Mutex<Option<(LanguageCacheKey, Vec<String>)>> = Mutex::new(None);
+
+/// The executable and the tessdata directory it reads packs from.
+type LanguageCacheKey = (String, Option<std::ffi::OsString>);
+
+fn language_cache_key(config: &AppConfig) -> LanguageCacheKey {
+    (config.tesseract_path.clone(), std::env::var_os("TESSDATA_PREFIX"))
+}
+
+fn list_installed_languages(config: &AppConfig) -> Result<Vec<String>, String> {
+    let key = language_cache_key(config);
+    if let Some((cached, langs)) = INSTALLED_LANGUAGES.lock().unwrap().as_ref() {
+        if *cached == key {
+            return Ok(langs.clone());
+        }
+    }
+    // Failures are not cached, so installing tesseract takes effect right away
+    let langs = query_installed_languages(config)?;
+    *INSTALLED_LANGUAGES.lock().unwrap() = Some((key, langs.clone()));
+    Ok(langs)
+}
+
+/// Forgets the installed packs, so packs added or removed since the last
+/// query show up. Called when settings are saved.
+pub fn clear_language_cache() {
+    *INSTALLED_LANGUAGES.lock().unwrap() = None;
+}
+
+fn query_installed_languages(config: &AppConfig) -> Result<Vec<String>, String> {
+    let output = tesseract_command(config)
+        .arg("--list-langs")
+        .output()
+        .map_err(|e| format!("tesseract not available: {}", e))?;
+
+    // Older versions print the list on stderr
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    Ok(String::from_utf8_lossy(&text)
+        .lines()
+        .skip(1) // "List of available languages in ..."
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+/// Tesseract pack names are ISO 639-2 plus script suffixes; map the common
+/// ones to the BCP-47 tags the other engines report.
+fn to_language_tag(code: &str) -> String {
+    match code {
+        "eng" => "en",
+        "chi_sim" | "chi_sim_vert" => "zh-Hans",
+        "chi_tra" | "chi_tra_vert" => "zh-Hant",
+        "jpn" | "jpn_vert" => "ja",
+        "kor" | "kor_vert" => "ko",
+        "deu" => "de",
+        "fra" => "fr",
+        "spa" => "es",
+        "rus" => "ru",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Builds lines from TSV rows. Level 4 rows are lines, level 5 rows are the
+/// words inside them; both carry `left top width height` in image pixels.
+fn parse_tsv(tsv: &str, width: u32, height: u32) -> OcrDocument {
+    let mut doc = OcrDocument::new("tesseract", width, height);
+    let mut current_key = None;
+
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 {
+            continue;
+        }
+        let num = |i: usize| cols[i].trim().parse::<f32>().unwrap_or(0.0);
+        let level = cols[0].trim();
+        let key = (cols[2].to_string(), cols[3].to_string(), cols[4].to_string());
+        let polygon = rect_polygon(num(6), num(7), num(8), num(9));
+
+        match level {
+            "4" => {
+                doc.lines.push(OcrLine { text: String::new(), words: Vec::new(), polygon, confidence: None });
+                current_key = Some(key);
+            }
+            "5" if current_key.as_ref() == Some(&key) => {
+                let text = cols[11].trim();
+                if text.is_empty() {
+                    continue;
+                }
+                let conf = num(10);
+                if let Some(line) = doc.lines.last_mut() {
+                    line.words.push(OcrWord {
+                        text: text.to_string(),
+                        polygon,
+                        confidence: (conf >= 0.0).then_some(conf / 100.0),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for line in &mut doc.lines {
+        // Tesseract splits CJK into one "word" per glyph; the spaces this puts
+        // between them are removed by the text normalizer
+        line.text = line.words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
+
+        let scores: Vec<f32> = line.words.iter().filter_map(|w| w.confidence).collect();
+        if !scores.is_empty() {
+            line.confidence = Some(scores.iter().sum::<f32>() / scores.len() as f32);
+        }
+    }
+    doc.lines.retain(|l| !l.text.trim().is_empty());
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr_document::polygon_bounds;
+
+    // Trimmed `tesseract ... tsv` output: two lines of text and one holding
+    // only an empty word
+    const TSV: &str = concat!(
+        "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n",
+        "1\t1\t0\t0\t0\t0\t0\t0\t400\t100\t-1\t\n",
+        "2\t1\t1\t0\t0\t0\t10\t12\t300\t70\t-1\t\n",
+        "3\t1\t1\t1\t0\t0\t10\t12\t300\t70\t-1\t\n",
+        "4\t1\t1\t1\t1\t0\t10\t12\t240\t24\t-1\t\n",
+        "5\t1\t1\t1\t1\t1\t10\t12\t100\t24\t96.5\tHello\n",
+        "5\t1\t1\t1\t1\t2\t120\t14\t130\t22\t91\tworld\n",
+        "4\t1\t1\t1\t2\t0\t10\t50\t300\t32\t-1\t\n",
+        "5\t1\t1\t1\t2\t1\t10\t50\t60\t32\t-1\t截图\n",
+        "5\t1\t1\t1\t2\t2\t80\t50\t230\t32\t95\t \n",
+        "4\t1\t1\t1\t3\t0\t10\t90\t50\t10\t-1\t\n",
+        "5\t1\t1\t1\t3\t1\t10\t90\t50\t10\t95\t\n",
+    );
+
+    #[test]
+    fn tsv_rows_become_lines_and_words() {
+        let doc = parse_tsv(TSV, 400, 100);
+        assert_eq!(doc.lines.len(), 2);
+
+        let first = &doc.lines[0];
+        assert_eq!(first.text, "Hello world");
+        assert_eq!(polygon_bounds(&first.polygon), Some((10.0, 12.0, 250.0, 36.0)));
+        assert_eq!(first.words.len(), 2);
+        assert_eq!(polygon_bounds(&first.words[1].polygon), Some((120.0, 14.0, 250.0, 36.0)));
+        assert!((first.words[0].confidence.unwrap() - 0.965).abs() < 1e-6);
+        assert!((first.confidence.unwrap() - 0.9375).abs() < 1e-6);
+    }
+
+    #[test]
+    fn unknown_confidence_and_empty_words() {
+        let doc = parse_tsv(TSV, 400, 100);
+        // `conf = -1` means no score; the second line's blank word is dropped,
+        // and the third line had nothing else so it goes too
+        let second = &doc.lines[1];
+        assert_eq!(second.text, "截图");
+        assert_eq!(second.words.len(), 1);
+        assert_eq!(second.words[0].confidence, None);
+        assert_eq!(second.confidence, None);
+    }
+}
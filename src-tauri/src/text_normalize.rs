@@ -0,0 +1,389 @@
+use unicode_normalization::UnicodeNormalization;
+
+use crate::config::AppConfig;
+use crate::ocr_document::OcrDocument;
+
+/// How punctuation width is adjusted to its surroundings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PunctuationWidth {
+    Keep,
+    /// Full-width after CJK text, half-width after Latin text.
+    Auto,
+    /// Full-width wherever it touches CJK text.
+    Full,
+    /// Always half-width.
+    Half,
+}
+
+/// What to do with spaces between CJK and Latin text ("用 Rust 写").
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CjkLatinSpacing {
+    Keep,
+    Remove,
+    Insert,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizeOptions {
+    pub nfkc: bool,
+    pub punctuation: PunctuationWidth,
+    pub cjk_latin_spacing: CjkLatinSpacing,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self { nfkc: false, punctuation: PunctuationWidth::Auto, cjk_latin_spacing: CjkLatinSpacing::Keep }
+    }
+}
+
+impl NormalizeOptions {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            nfkc: config.ocr_nfkc,
+            punctuation: match config.ocr_punctuation_width.as_str() {
+                "keep" => PunctuationWidth::Keep,
+                "full" => PunctuationWidth::Full,
+                "half" => PunctuationWidth::Half,
+                _ => PunctuationWidth::Auto,
+            },
+            cjk_latin_spacing: match config.ocr_cjk_latin_spacing.as_str() {
+                "remove" => CjkLatinSpacing::Remove,
+                "insert" => CjkLatinSpacing::Insert,
+                _ => CjkLatinSpacing::Keep,
+            },
+        }
+    }
+}
+
+/// Ideographs (including Extension A and compatibility ideographs), kana and
+/// Hangul: the letters that punctuation width follows.
+pub fn is_cjk_letter(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'     // CJK Unified Ideographs
+        | '\u{3400}'..='\u{4DBF}'   // Extension A
+        | '\u{20000}'..='\u{2FA1F}' // Extensions B-F, compatibility supplement
+        | '\u{F900}'..='\u{FAFF}'   // Compatibility Ideographs
+        | '\u{3040}'..='\u{309F}'   // Hiragana
+        | '\u{30A0}'..='\u{30FF}'   // Katakana
+        | '\u{31F0}'..='\u{31FF}'   // Katakana Phonetic Extensions
+        | '\u{FF66}'..='\u{FF9F}'   // Halfwidth Katakana
+        | '\u{AC00}'..='\u{D7AF}'   // Hangul Syllables
+        | '\u{1100}'..='\u{11FF}'   // Hangul Jamo
+        | '\u{3130}'..='\u{318F}'   // Hangul Compatibility Jamo
+    )
+}
+
+/// Full-width and CJK punctuation, which never takes surrounding spaces.
+pub fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{303F}'     // CJK Symbols and Punctuation
+        | '\u{FF01}'..='\u{FF0F}'
+        | '\u{FF1A}'..='\u{FF20}'
+        | '\u{FF3B}'..='\u{FF40}'
+        | '\u{FF5B}'..='\u{FF65}'
+        | '\u{FE10}'..='\u{FE1F}'   // Vertical Forms
+        | '\u{FE30}'..='\u{FE4F}'   // CJK Compatibility Forms
+    )
+}
+
+pub fn is_cjk(c: char) -> bool {
+    is_cjk_letter(c) || is_cjk_punctuation(c)
+}
+
+/// Korean separates words with spaces, unlike Chinese and Japanese.
+pub fn is_hangul(c: char) -> bool {
+    matches!(c, '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}')
+}
+
+/// CJK text that is written without spaces between words: everything but Hangul.
+pub fn is_unspaced(c: char) -> bool {
+    is_cjk(c) && !is_hangul(c)
+}
+
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphanumeric() || (c.is_alphanumeric() && !is_cjk(c))
+}
+
+/// Half-width punctuation and its full-width counterpart.
+const PUNCTUATION_PAIRS: &[(char, char)] = &[
+    (',', '，'),
+    ('.', '。'),
+    ('!', '！'),
+    ('?', '？'),
+    (':', '：'),
+    (';', '；'),
+    ('(', '（'),
+    (')', '）'),
+];
+
+fn to_full(c: char) -> Option<char> {
+    PUNCTUATION_PAIRS.iter().find(|(h, _)| *h == c).map(|(_, f)| *f)
+}
+
+fn to_half(c: char) -> Option<char> {
+    match c {
+        '、' => Some(','),
+        '．' => Some('.'),
+        _ => PUNCTUATION_PAIRS.iter().find(|(_, f)| *f == c).map(|(h, _)| *h),
+    }
+}
+
+/// Full-width ASCII letters and digits become their plain forms; OCR engines
+/// emit them for CJK fonts, and they break search and translation.
+fn narrow_alphanumeric(c: char) -> char {
+    match c {
+        '\u{FF10}'..='\u{FF19}' | '\u{FF21}'..='\u{FF3A}' | '\u{FF41}'..='\u{FF5A}' => {
+            char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)
+        }
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+pub fn normalize(text: &str, options: &NormalizeOptions) -> String {
+    let text: String = if options.nfkc { text.nfkc().collect() } else { text.to_string() };
+    text.split('\n')
+        .map(|line| normalize_line(line, options))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn normalize_line(line: &str, options: &NormalizeOptions) -> String {
+    let chars: Vec<char> = line.chars().map(narrow_alphanumeric).collect();
+    let chars = convert_punctuation(&chars, options.punctuation);
+    let chars = fix_spacing(&chars, options);
+    chars.into_iter().collect::<String>().trim().to_string()
+}
+
+fn convert_punctuation(chars: &[char], mode: PunctuationWidth) -> Vec<char> {
+    if mode == PunctuationWidth::Keep {
+        return chars.to_vec();
+    }
+    let prev_visible = |i: usize| chars[..i].iter().rev().find(|c| !c.is_whitespace()).copied();
+    let next_visible = |i: usize| chars[i + 1..].iter().find(|c| !c.is_whitespace()).copied();
+
+    let mut out = Vec::with_capacity(chars.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = prev_visible(i);
+        let next = next_visible(i);
+        let prev_cjk = prev.is_some_and(is_cjk_letter);
+        let next_cjk = next.is_some_and(is_cjk_letter);
+        let prev_latin = prev.is_some_and(is_latin);
+
+        let converted = match mode {
+            PunctuationWidth::Half => to_half(c),
+            PunctuationWidth::Full => to_full(c).filter(|_| match c {
+                // Opening brackets belong to what follows, closing ones to what precedes
+                '(' => next_cjk,
+                ')' => prev_cjk,
+                _ => prev_cjk || (next_cjk && !prev_latin),
+            }),
+            PunctuationWidth::Auto => match to_full(c) {
+                Some(full) => {
+                    let cjk = match c {
+                        '(' => next_cjk,
+                        ')' => prev_cjk,
+                        // "第3.5版" and "12:30" keep their ASCII separators
+                        _ => prev_cjk && next.is_none_or(|n| is_cjk(n) || !n.is_ascii_alphanumeric()),
+                    };
+                    cjk.then_some(full)
+                }
+                None => to_half(c).filter(|_| match c {
+                    '（' => next.is_some_and(is_latin),
+                    '）' => prev_latin,
+                    _ => prev_latin && !next_cjk,
+                }),
+            },
+            PunctuationWidth::Keep => None,
+        };
+        out.push(converted.unwrap_or(c));
+
+        // "Hello，world" -> "Hello, world"
+        if converted.is_some_and(|h| matches!(h, ',' | ';' | ':' | '!' | '?'))
+            && chars.get(i + 1).is_some_and(|n| n.is_alphabetic() && !is_cjk(*n))
+        {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+fn fix_spacing(chars: &[char], options: &NormalizeOptions) -> Vec<char> {
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if !c.is_whitespace() {
+            out.push(c);
+            i += 1;
+            continue;
+        }
+
+        // Collapse the whole run of spaces and look at what it separates
+        let start = i;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        let (Some(&prev), Some(&next)) = (out.last(), chars.get(i)) else { continue };
+
+        // Chinese and Japanese text and full-width punctuation never take spaces
+        let keep = if (is_unspaced(prev) && is_unspaced(next)) || is_cjk_punctuation(prev) || is_cjk_punctuation(next) {
+            false
+        } else if (is_cjk_letter(prev) && is_latin(next)) || (is_latin(prev) && is_cjk_letter(next)) {
+            match options.cjk_latin_spacing {
+                CjkLatinSpacing::Remove => false,
+                CjkLatinSpacing::Keep | CjkLatinSpacing::Insert => true,
+            }
+        } else {
+            true
+        };
+        if keep {
+            out.push(if chars[start] == '\t' { '\t' } else { ' ' });
+        }
+    }
+
+    if options.cjk_latin_spacing != CjkLatinSpacing::Insert {
+        return out;
+    }
+    let mut spaced = Vec::with_capacity(out.len());
+    for (i, &c) in out.iter().enumerate() {
+        if let Some(&prev) = i.checked_sub(1).and_then(|p| out.get(p)) {
+            if (is_cjk_letter(prev) && is_latin(c)) || (is_latin(prev) && is_cjk_letter(c)) {
+                spaced.push(' ');
+            }
+        }
+        spaced.push(c);
+    }
+    spaced
+}
+
+/// Normalizes every line and word of an engine result in place.
+pub fn normalize_document(doc: &mut OcrDocument, options: &NormalizeOptions) {
+    for line in &mut doc.lines {
+        line.text = normalize(&line.text, options);
+        for word in &mut line.words {
+            word.text = normalize(&word.text, options);
+        }
+    }
+    doc.lines.retain(|l| !l.text.is_empty());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn norm(text: &str) -> String {
+        normalize(text, &NormalizeOptions::default())
+    }
+
+    fn with(punctuation: PunctuationWidth, spacing: CjkLatinSpacing) -> NormalizeOptions {
+        NormalizeOptions { nfkc: false, punctuation, cjk_latin_spacing: spacing }
+    }
+
+    #[test]
+    fn removes_spaces_between_cjk() {
+        assert_eq!(norm("你 好 世 界"), "你好世界");
+        assert_eq!(norm("こ ん に ち は"), "こんにちは");
+        assert_eq!(norm("カ タ カ ナ"), "カタカナ");
+        assert_eq!(norm("㐀 㐁"), "㐀㐁"); // Extension A
+    }
+
+    #[test]
+    fn keeps_spaces_between_korean_words() {
+        assert_eq!(norm("안녕 하세요"), "안녕 하세요");
+        assert_eq!(norm("안녕  하세요"), "안녕 하세요");
+    }
+
+    #[test]
+    fn keeps_spaces_between_latin_words() {
+        assert_eq!(norm("Hello   world"), "Hello world");
+        assert_eq!(norm("  padded  "), "padded");
+    }
+
+    #[test]
+    fn removes_spaces_around_fullwidth_punctuation() {
+        assert_eq!(norm("你好 ， 世界 。"), "你好，世界。");
+        assert_eq!(norm("「 引用 」"), "「引用」");
+    }
+
+    #[test]
+    fn cjk_latin_spacing_modes() {
+        let keep = with(PunctuationWidth::Keep, CjkLatinSpacing::Keep);
+        let remove = with(PunctuationWidth::Keep, CjkLatinSpacing::Remove);
+        let insert = with(PunctuationWidth::Keep, CjkLatinSpacing::Insert);
+        assert_eq!(normalize("使用 Rust 编写", &keep), "使用 Rust 编写");
+        assert_eq!(normalize("使用Rust编写", &keep), "使用Rust编写");
+        assert_eq!(normalize("使用 Rust 编写", &remove), "使用Rust编写");
+        assert_eq!(normalize("使用Rust编写", &insert), "使用 Rust 编写");
+        assert_eq!(normalize("使用 Rust 编写", &insert), "使用 Rust 编写");
+        assert_eq!(normalize("第3章", &insert), "第 3 章");
+    }
+
+    #[test]
+    fn auto_punctuation_follows_context() {
+        assert_eq!(norm("你好,世界."), "你好，世界。");
+        assert_eq!(norm("Hello，world！"), "Hello, world!");
+        assert_eq!(norm("它说(很好)"), "它说（很好）");
+        assert_eq!(norm("call（foo）"), "call(foo)");
+    }
+
+    #[test]
+    fn auto_punctuation_leaves_numbers_alone() {
+        assert_eq!(norm("版本3.5发布"), "版本3.5发布");
+        assert_eq!(norm("时间12:30"), "时间12:30");
+        assert_eq!(norm("pi = 3.14"), "pi = 3.14");
+    }
+
+    #[test]
+    fn full_and_half_modes() {
+        let full = with(PunctuationWidth::Full, CjkLatinSpacing::Keep);
+        let half = with(PunctuationWidth::Half, CjkLatinSpacing::Keep);
+        assert_eq!(normalize("好的,谢谢!", &full), "好的，谢谢！");
+        assert_eq!(normalize("a,b", &full), "a,b");
+        assert_eq!(normalize("好的，谢谢！", &half), "好的,谢谢!");
+        assert_eq!(normalize("一、二", &half), "一,二");
+    }
+
+    #[test]
+    fn fullwidth_alphanumerics_are_narrowed() {
+        assert_eq!(norm("ＡＢＣ１２３"), "ABC123");
+        assert_eq!(norm("全角\u{3000}空格"), "全角空格");
+    }
+
+    #[test]
+    fn nfkc_option() {
+        let nfkc = NormalizeOptions { nfkc: true, ..NormalizeOptions::default() };
+        // Halfwidth katakana and ligatures
+        assert_eq!(normalize("ｶﾀｶﾅ", &nfkc), "カタカナ");
+        assert_eq!(normalize("ﬁle", &nfkc), "file");
+        assert_eq!(norm("ﬁle"), "ﬁle");
+        // NFKC narrows "，", auto mode widens it again after CJK
+        assert_eq!(normalize("你好，世界", &nfkc), "你好，世界");
+    }
+
+    #[test]
+    fn keeps_line_breaks() {
+        assert_eq!(norm("第一 行\n第二 行"), "第一行\n第二行");
+    }
+
+    #[test]
+    fn keep_mode_is_passthrough_for_punctuation() {
+        let keep = with(PunctuationWidth::Keep, CjkLatinSpacing::Keep);
+        assert_eq!(normalize("你好,world，ok", &keep), "你好,world，ok");
+    }
+
+    #[test]
+    fn character_classes() {
+        assert!(is_cjk_letter('漢'));
+        assert!(is_cjk_letter('ひ'));
+        assert!(is_cjk_letter('ア'));
+        assert!(is_cjk_letter('한'));
+        assert!(is_hangul('한'));
+        assert!(!is_unspaced('한'));
+        assert!(is_unspaced('漢'));
+        assert!(!is_cjk_letter('A'));
+        assert!(is_cjk_punctuation('，'));
+        assert!(is_cjk_punctuation('。'));
+        assert!(!is_cjk_punctuation(','));
+    }
+}
@@ -0,0 +1,207 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+
+/// A rectangle in physical desktop pixels, like `ocr_core::MonitorInfo`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ScreenRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A visible top-level window the capture overlay can snap to.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapWindow {
+    pub title: String,
+    /// Visible frame, without the invisible resize borders where the window
+    /// system reports them.
+    pub rect: ScreenRect,
+    /// Visible child controls (buttons, text fields, panes), nested ones too.
+    /// Empty where the platform has no native child windows.
+    pub controls: Vec<ScreenRect>,
+}
+
+/// Windows as they were when the last screenshot was taken. The overlay
+/// covers the desktop afterwards, so enumerating later would only find it.
+static LAST_WINDOWS: Lazy<Mutex<Vec<SnapWindow>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Enumerates the visible windows, topmost first, and caches them for
+/// `snapshot`. Call right before taking the screenshot.
+pub fn refresh() {
+    let start = std::time::Instant::now();
+    let windows = enumerate();
+    println!("window_snap: {} window(s) enumerated in {:?}", windows.len(), start.elapsed());
+    *LAST_WINDOWS.lock().unwrap() = windows;
+}
+
+/// The windows cached by the last `refresh`, topmost first.
+pub fn snapshot() -> Vec<SnapWindow> {
+    LAST_WINDOWS.lock().unwrap().clone()
+}
+
+#[cfg(windows)]
+fn enumerate() -> Vec<SnapWindow> {
+    use windows::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
+    use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED, DWMWA_EXTENDED_FRAME_BOUNDS};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumChildWindows, EnumWindows, GetWindowRect, GetWindowTextW, IsIconic, IsWindowVisible,
+    };
+
+    /// Controls smaller than this on either side are not worth snapping to.
+    const MIN_CONTROL_SIZE: u32 = 8;
+
+    fn to_rect(r: RECT) -> Option<ScreenRect> {
+        (r.right > r.left && r.bottom > r.top).then(|| ScreenRect {
+            x: r.left,
+            y: r.top,
+            width: (r.right - r.left) as u32,
+            height: (r.bottom - r.top) as u32,
+        })
+    }
+
+    unsafe extern "system" fn on_child(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let controls = &mut *(lparam.0 as *mut Vec<ScreenRect>);
+        let mut r = RECT::default();
+        if IsWindowVisible(hwnd).as_bool() && GetWindowRect(hwnd, &mut r).is_ok() {
+            if let Some(rect) = to_rect(r).filter(|r| r.width >= MIN_CONTROL_SIZE && r.height >= MIN_CONTROL_SIZE) {
+                controls.push(rect);
+            }
+        }
+        true.into()
+    }
+
+    unsafe extern "system" fn on_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let windows = &mut *(lparam.0 as *mut Vec<SnapWindow>);
+        if !IsWindowVisible(hwnd).as_bool() || IsIconic(hwnd).as_bool() {
+            return true.into();
+        }
+        // Suspended UWP apps and windows on other virtual desktops are
+        // "visible" but cloaked
+        let mut cloaked = 0u32;
+        let cloaked_ok = DwmGetWindowAttribute(hwnd, DWMWA_CLOAKED, &mut cloaked as *mut u32 as *mut _, std::mem::size_of::<u32>() as u32).is_ok();
+        if cloaked_ok && cloaked != 0 {
+            return true.into();
+        }
+
+        // GetWindowRect includes the invisible resize borders on Windows 10+
+        let mut r = RECT::default();
+        let framed = DwmGetWindowAttribute(hwnd, DWMWA_EXTENDED_FRAME_BOUNDS, &mut r as *mut RECT as *mut _, std::mem::size_of::<RECT>() as u32).is_ok();
+        if !framed && GetWindowRect(hwnd, &mut r).is_err() {
+            return true.into();
+        }
+        let Some(rect) = to_rect(r) else {
+            return true.into();
+        };
+
+        let mut title = [0u16; 256];
+        let len = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+        let mut controls = Vec::new();
+        let _ = EnumChildWindows(hwnd, Some(on_child), LPARAM(&mut controls as *mut Vec<ScreenRect> as isize));
+
+        windows.push(SnapWindow { title: String::from_utf16_lossy(&title[..len]), rect, controls });
+        true.into()
+    }
+
+    let mut windows: Vec<SnapWindow> = Vec::new();
+    // EnumWindows walks top-level windows in z-order, topmost first
+    if let Err(e) = unsafe { EnumWindows(Some(on_window), LPARAM(&mut windows as *mut Vec<SnapWindow> as isize)) } {
+        println!("window_snap: EnumWindows failed: {}", e);
+    }
+    windows
+}
+
+#[cfg(target_os = "linux")]
+fn enumerate() -> Vec<SnapWindow> {
+    match x11::enumerate() {
+        Ok(windows) => windows,
+        Err(e) => {
+            // Wayland sessions without XWayland, or no display at all
+            println!("window_snap: X11 enumeration unavailable: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn enumerate() -> Vec<SnapWindow> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use super::{ScreenRect, SnapWindow};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, MapState, Window, WindowClass};
+    use x11rb::rust_connection::RustConnection;
+
+    pub fn enumerate() -> Result<Vec<SnapWindow>, String> {
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| e.to_string())?;
+        let root = conn.setup().roots[screen_num].root;
+        let atom = |name: &str| -> Result<u32, String> {
+            Ok(conn.intern_atom(false, name.as_bytes()).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?.atom)
+        };
+        let client_list = atom("_NET_CLIENT_LIST_STACKING")?;
+        let frame_extents = atom("_NET_FRAME_EXTENTS")?;
+        let net_wm_name = atom("_NET_WM_NAME")?;
+        let utf8_string = atom("UTF8_STRING")?;
+
+        // EWMH window managers list client windows bottom to top; without one,
+        // the root's children are the windows, also bottom to top
+        let mut clients = u32_property(&conn, root, client_list, AtomEnum::WINDOW.into());
+        if clients.is_empty() {
+            clients = conn.query_tree(root).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?.children;
+        }
+
+        let mut windows = Vec::new();
+        for &window in clients.iter().rev() {
+            let Ok(Some(rect)) = client_rect(&conn, root, window, frame_extents) else {
+                continue;
+            };
+            let mut title = string_property(&conn, window, net_wm_name, utf8_string);
+            if title.is_empty() {
+                title = string_property(&conn, window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into());
+            }
+            windows.push(SnapWindow { title, rect, controls: Vec::new() });
+        }
+        Ok(windows)
+    }
+
+    /// Root-relative bounds of a mapped window, grown by the decorations the
+    /// window manager reports in `_NET_FRAME_EXTENTS`.
+    fn client_rect(conn: &RustConnection, root: Window, window: Window, frame_extents: u32) -> Result<Option<ScreenRect>, String> {
+        let attributes = conn.get_window_attributes(window).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?;
+        if attributes.map_state != MapState::VIEWABLE || attributes.class == WindowClass::INPUT_ONLY {
+            return Ok(None);
+        }
+        let geometry = conn.get_geometry(window).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?;
+        let origin = conn.translate_coordinates(window, root, 0, 0).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?;
+
+        // left, right, top, bottom
+        let extents = u32_property(conn, window, frame_extents, AtomEnum::CARDINAL.into());
+        let [left, right, top, bottom] = <[u32; 4]>::try_from(extents.as_slice()).unwrap_or_default();
+        let width = geometry.width as u32 + left + right;
+        let height = geometry.height as u32 + top + bottom;
+        if width == 0 || height == 0 {
+            return Ok(None);
+        }
+        Ok(Some(ScreenRect { x: origin.dst_x as i32 - left as i32, y: origin.dst_y as i32 - top as i32, width, height }))
+    }
+
+    fn u32_property(conn: &RustConnection, window: Window, property: u32, kind: u32) -> Vec<u32> {
+        conn.get_property(false, window, property, kind, 0, u32::MAX)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| reply.value32().map(|values| values.collect()))
+            .unwrap_or_default()
+    }
+
+    fn string_property(conn: &RustConnection, window: Window, property: u32, kind: u32) -> String {
+        conn.get_property(false, window, property, kind, 0, 1024)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+            .unwrap_or_default()
+    }
+}
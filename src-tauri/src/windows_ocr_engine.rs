@@ -0,0 +1,210 @@
+use image::DynamicImage;
+use serde::Serialize;
+use windows::Globalization::Language;
+use windows::Graphics::Imaging::BitmapDecoder;
+use windows::Media::Ocr::OcrEngine;
+use windows::Storage::Streams::{DataWriter, InMemoryRandomAccessStream};
+
+use crate::config::AppConfig;
+use crate::ocr_backend::{BackendHealth, OcrBackend, OcrCapabilities};
+use crate::ocr_document::{polygon_bounds, rect_polygon, OcrDocument, OcrLine, OcrWord};
+use crate::ocr_scoring::pick_best;
+
+pub struct WindowsBackend;
+
+impl OcrBackend for WindowsBackend {
+    fn name(&self) -> &'static str {
+        "windows"
+    }
+
+    fn capabilities(&self) -> OcrCapabilities {
+        OcrCapabilities {
+            bounding_boxes: true,
+            confidence: false,
+            language_selection: true,
+            persistent_process: false,
+        }
+    }
+
+    fn supported_languages(&self, _config: &AppConfig) -> Vec<String> {
+        available_languages()
+            .map(|langs| langs.into_iter().map(|l| l.tag).collect())
+            .unwrap_or_default()
+    }
+
+    fn health(&self, config: &AppConfig) -> BackendHealth {
+        let installed = match available_languages() {
+            Ok(langs) if langs.is_empty() => return BackendHealth::Unavailable("No OCR language packs installed".to_string()),
+            Ok(langs) => langs,
+            Err(e) => return BackendHealth::Unavailable(e),
+        };
+
+        if config.windows_ocr_languages.is_empty() {
+            return match OcrEngine::TryCreateFromUserProfileLanguages() {
+                Ok(_) => BackendHealth::Ready,
+                Err(_) => BackendHealth::Degraded("No OCR language pack matches the user profile".to_string()),
+            };
+        }
+
+        let missing: Vec<&str> = config.windows_ocr_languages
+            .iter()
+            .map(|l| l.as_str())
+            .filter(|l| !installed.iter().any(|i| i.tag.eq_ignore_ascii_case(l)))
+            .collect();
+        if missing.is_empty() {
+            BackendHealth::Ready
+        } else {
+            BackendHealth::Degraded(format!("OCR language packs not installed: {}", missing.join(", ")))
+        }
+    }
+
+    fn recognize(&self, image: &DynamicImage, config: &AppConfig) -> Result<OcrDocument, String> {
+        run_windows_ocr_logic(image, &config.windows_ocr_languages)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrLanguageInfo {
+    pub tag: String,
+    pub name: String,
+}
+
+/// Languages with an installed OCR pack, as reported by Windows.
+pub fn available_languages() -> Result<Vec<OcrLanguageInfo>, String> {
+    let languages = OcrEngine::AvailableRecognizerLanguages().map_err(|e| e.to_string())?;
+    Ok(languages
+        .into_iter()
+        .filter_map(|lang| {
+            let tag = lang.LanguageTag().ok()?.to_string();
+            let name = lang.DisplayName().map(|n| n.to_string()).unwrap_or_else(|_| tag.clone());
+            Some(OcrLanguageInfo { tag, name })
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn list_windows_ocr_languages() -> Result<Vec<OcrLanguageInfo>, String> {
+    available_languages()
+}
+
+fn run_windows_ocr_logic(image: &DynamicImage, languages: &[String]) -> Result<OcrDocument, String> {
+    println!("windows_ocr: Running Windows OCR logic...");
+
+    // Scaling, grayscale and dark-mode inversion happen before we get the image
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).map_err(|e| e.to_string())?;
+
+    // Run on a dedicated COM-initialized thread; WinRT async ops are driven with block_on there.
+    let languages = languages.to_vec();
+    std::thread::spawn(move || run_on_com_thread(png, languages))
+        .join()
+        .map_err(|_| "Windows OCR thread panicked".to_string())?
+}
+
+fn run_on_com_thread(png_bytes: Vec<u8>, languages: Vec<String>) -> Result<OcrDocument, String> {
+    use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
+    unsafe { let _ = CoInitializeEx(None, COINIT_MULTITHREADED); }
+    tauri::async_runtime::block_on(async {
+        run_windows_native_ocr_v2(png_bytes, &languages).await
+    })
+}
+
+async fn run_windows_native_ocr_v2(png_bytes: Vec<u8>, languages: &[String]) -> Result<OcrDocument, String> {
+    let stream = InMemoryRandomAccessStream::new().map_err(|e: windows::core::Error| e.to_string())?;
+    let writer = stream.GetOutputStreamAt(0).map_err(|e: windows::core::Error| e.to_string())?;
+    let data_writer = DataWriter::CreateDataWriter(&writer).map_err(|e: windows::core::Error| e.to_string())?;
+    data_writer.WriteBytes(&png_bytes).map_err(|e: windows::core::Error| e.to_string())?;
+    data_writer.StoreAsync().map_err(|e: windows::core::Error| e.to_string())?.await.map_err(|e: windows::core::Error| e.to_string())?;
+    data_writer.FlushAsync().map_err(|e: windows::core::Error| e.to_string())?.await.map_err(|e: windows::core::Error| e.to_string())?;
+
+    let decoder = BitmapDecoder::CreateAsync(&stream).map_err(|e: windows::core::Error| e.to_string())?.await.map_err(|e: windows::core::Error| e.to_string())?;
+    let bitmap = decoder.GetSoftwareBitmapAsync().map_err(|e: windows::core::Error| e.to_string())?.await.map_err(|e: windows::core::Error| e.to_string())?;
+    let width = bitmap.PixelWidth().unwrap_or(0) as u32;
+    let height = bitmap.PixelHeight().unwrap_or(0) as u32;
+
+    let engines = create_engines(languages);
+
+    let mut results = Vec::new();
+    for (label, engine) in engines {
+        let label = label.as_str();
+        if let Ok(result) = engine.RecognizeAsync(&bitmap).map_err(|e| e.to_string())?.await {
+            let mut doc = OcrDocument::new("windows", width, height);
+            doc.language = engine.RecognizerLanguage().and_then(|l| l.LanguageTag()).map(|t| t.to_string()).ok();
+
+            // Use Lines() instead of Text() to ensure we get explicit line breaks
+            if let Ok(lines) = result.Lines() {
+                for line in lines {
+                    if let Some(ocr_line) = convert_line(&line) {
+                        doc.lines.push(ocr_line);
+                    }
+                }
+            }
+
+            let text_len = doc.text().len();
+            println!("windows_ocr: {} Text Len: {}", label, text_len);
+            if text_len > 0 {
+                results.push(doc);
+            }
+        }
+    }
+
+    if let Some((best, _)) = pick_best(&results) {
+        let doc = results.swap_remove(best);
+        println!("windows_ocr: best result selected. language: {:?}", doc.language);
+        Ok(doc)
+    } else {
+        println!("windows_ocr: no recognition results from any engine");
+        Ok(OcrDocument::new("windows", width, height))
+    }
+}
+
+/// One engine per configured language, skipping those without an installed
+/// pack. With nothing configured (or nothing usable) the user-profile
+/// languages are used.
+fn create_engines(languages: &[String]) -> Vec<(String, OcrEngine)> {
+    let mut engines = Vec::new();
+    for tag in languages {
+        let engine = Language::CreateLanguage(&windows::core::HSTRING::from(tag.as_str()))
+            .and_then(|lang| OcrEngine::TryCreateFromLanguage(&lang));
+        match engine {
+            Ok(engine) => engines.push((tag.clone(), engine)),
+            Err(_) => println!("windows_ocr: No OCR pack for '{}', skipping.", tag),
+        }
+    }
+
+    if engines.is_empty() {
+        if let Ok(engine) = OcrEngine::TryCreateFromUserProfileLanguages() {
+            engines.push(("UserProfile".to_string(), engine));
+        }
+    }
+    engines
+}
+
+fn convert_line(line: &windows::Media::Ocr::OcrLine) -> Option<OcrLine> {
+    let text = line.Text().ok()?.to_string();
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let mut words = Vec::new();
+    if let Ok(ocr_words) = line.Words() {
+        for word in ocr_words {
+            let (Ok(word_text), Ok(rect)) = (word.Text(), word.BoundingRect()) else { continue };
+            words.push(OcrWord {
+                text: word_text.to_string(),
+                polygon: rect_polygon(rect.X, rect.Y, rect.Width, rect.Height),
+                confidence: None,
+            });
+        }
+    }
+
+    // Windows OCR has no line geometry of its own; use the union of the word boxes.
+    let polygon = words
+        .iter()
+        .filter_map(|w| polygon_bounds(&w.polygon))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+        .map(|(x0, y0, x1, y1)| rect_polygon(x0, y0, x1 - x0, y1 - y0))
+        .unwrap_or_default();
+
+    Some(OcrLine { text, words, polygon, confidence: None })
+}